@@ -5,6 +5,11 @@
 @interface LocationManager () <CLLocationManagerDelegate>
 
 @property(strong, nonatomic) CLLocationManager *locationManager;
+// set while request_location waits for a one-shot fix
+@property(strong, nonatomic) dispatch_semaphore_t requestSemaphore;
+@property(strong, nonatomic) CLLocation *requestedLocation;
+// whether rust gets every update, otherwise the updates only answer requests
+@property(nonatomic) BOOL subscribed;
 
 @end
 
@@ -27,46 +32,69 @@
 
 - (void)subscribe {
     NSLog(@"LocationManager subscribe");
+    self.subscribed = YES;
     [self.locationManager startMonitoringSignificantLocationChanges];
 }
 
 - (void)unsubscribe {
+    self.subscribed = NO;
     [self.locationManager stopMonitoringSignificantLocationChanges];
 }
 
+- (CLLocation *)requestLocation {
+    self.requestedLocation = nil;
+    self.requestSemaphore = dispatch_semaphore_create(0);
+    dispatch_async(dispatch_get_main_queue(), ^{
+        [self.locationManager requestLocation];
+    });
+    dispatch_semaphore_wait(self.requestSemaphore, dispatch_time(DISPATCH_TIME_NOW, 30 * NSEC_PER_SEC));
+    self.requestSemaphore = nil;
+    return self.requestedLocation;
+}
+
 - (void)locationManagerDidChangeAuthorization:(CLLocationManager *)manager {
     NSLog(@"LocationManager didChangeAuthorizationStatus: %d", manager.authorizationStatus);
 }
 
 - (void)locationManager:(CLLocationManager *)manager didUpdateLocations:(NSArray*)locations {
+    // the fix request_location is waiting for, rust sends it to the group that asked
+    if (self.requestSemaphore) {
+        self.requestedLocation = [locations lastObject];
+        dispatch_semaphore_signal(self.requestSemaphore);
+    }
+    // the updates of a subscription still have to be sent while a request is pending
+    if (!self.subscribed) {
+        return;
+    }
+
     NSMutableArray *updates = [NSMutableArray array];
     for (CLLocation *location in locations) {
+        // CoreLocation marks a value it couldn't determine with a negative number, zero is a real reading
+        if (location.horizontalAccuracy < 0) {
+            continue;
+        }
         NSMutableDictionary *update = [NSMutableDictionary dictionary];
         update[@"longitude"] = @(location.coordinate.longitude);
         update[@"latitude"] = @(location.coordinate.latitude);
         update[@"timestamp"] = @(location.timestamp.timeIntervalSince1970);
-        if (location.altitude) {
+        update[@"horizontal_accuracy"] = @(location.horizontalAccuracy);
+        if (location.verticalAccuracy >= 0) {
             update[@"altitude"] = @(location.altitude);
+            update[@"vertical_accuracy"] = @(location.verticalAccuracy);
         }
         if (location.floor) {
             update[@"floor"] = @(location.floor.level);
         }
-        if (location.course) {
+        if (location.course >= 0) {
             update[@"course"] = @(location.course);
         }
-        if (location.horizontalAccuracy) {
-            update[@"horizontal_accuracy"] = @(location.horizontalAccuracy);
-        }
-        if (location.verticalAccuracy) {
-            update[@"vertical_accuracy"] = @(location.verticalAccuracy);
-        }
-        if (location.courseAccuracy) {
+        if (location.courseAccuracy >= 0) {
             update[@"course_accuracy"] = @(location.courseAccuracy);
         }
-        if (location.speed) {
+        if (location.speed >= 0) {
             update[@"speed"] = @(location.speed);
         }
-        if (location.speedAccuracy) {
+        if (location.speedAccuracy >= 0) {
             update[@"speed_accuracy"] = @(location.speedAccuracy);
         }
         [updates addObject:update];
@@ -87,6 +115,9 @@
 
 - (void)locationManager:(CLLocationManager *)manager didFailWithError:(NSError *)error {
     NSLog(@"LocationManager didFailWithError: %@", error);
+    if (self.requestSemaphore) {
+        dispatch_semaphore_signal(self.requestSemaphore);
+    }
 }
 
 
@@ -124,6 +155,20 @@ void unsubscribe_from_location_updates(const void *location_manager) {
     [locationManager unsubscribe];
 }
 
+bool request_location(const void *location_manager, double *latitude, double *longitude, double *timestamp, double *horizontal_accuracy) {
+    LocationManager *locationManager = (__bridge LocationManager *) location_manager;
+    CLLocation *location = [locationManager requestLocation];
+    if (!location) {
+        return false;
+    }
+    *latitude = location.coordinate.latitude;
+    *longitude = location.coordinate.longitude;
+    // milliseconds
+    *timestamp = location.timestamp.timeIntervalSince1970 * 1000;
+    *horizontal_accuracy = location.horizontalAccuracy;
+    return true;
+}
+
 void destroy_location_manager(void *location_manager) {
     CFBridgingRelease(location_manager);
 }
@@ -1,7 +1,7 @@
 /* WARNING: This file is auto-generated. Do not modify. */
 
 import { NativeModules, Platform } from 'react-native';
-import { Result, Uuid, FrontendInstance, Base64 } from './index';
+import { Result, Uuid, FrontendInstance, Base64, LocationPrecision } from './index';
 
 const LINKING_ERROR =
     `The package 'react-native-bubble-rust' doesn't seem to be linked. Make sure: \n\n${Platform.select(
@@ -30,6 +30,44 @@ export interface Location {
     latitude: number,
     longitude: number,
     timestamp: number,
+    precision: LocationPrecision,
+    altitude: number | null,
+    floor: number | null,
+    course: number | null,
+    speed: number | null,
+    horizontal_accuracy: number | null,
+    vertical_accuracy: number | null,
+    course_accuracy: number | null,
+    speed_accuracy: number | null,
+}
+export interface DroppedMessage {
+    id: number,
+    group_uuid: Uuid,
+    kind: string,
+    error: string,
+    timestamp: number,
+}
+export interface Place {
+    uuid: Uuid,
+    name: string,
+    latitude: number,
+    longitude: number,
+    radius: number,
+}
+export interface PlaceEvent {
+    place_uuid: Uuid,
+    client_uuid: Uuid,
+    entered: boolean,
+    timestamp: number,
+}
+export interface TimelineSegment {
+    stay: boolean,
+    start_timestamp: number,
+    end_timestamp: number,
+    duration: number,
+    latitude: number | null,
+    longitude: number | null,
+    distance: number,
 }
 export interface UserOut {
     uuid: Uuid,
@@ -38,6 +76,12 @@ export interface UserOut {
     primary_client_uuid: Uuid | null,
     identity: Base64,
 }
+export interface RetentionSettings {
+    keep_raw: boolean,
+    downsample_after_days: number | null,
+    downsample_interval_minutes: number,
+    delete_after_days: number | null,
+}
 export interface AccountData {
     domain: string,
     user_uuid: Uuid,
@@ -48,6 +92,23 @@ export interface Status {
     data_directory: string,
     account_data: AccountData | null,
 }
+export interface ChatMessage {
+    uuid: Uuid,
+    client_uuid: Uuid,
+    body: string,
+    reply_to: Uuid | null,
+    timestamp: number,
+}
+export interface ReceiptSettings {
+    send_delivery_receipts: boolean,
+    send_read_receipts: boolean,
+}
+export interface MessageReceipt {
+    user_uuid: Uuid,
+    client_uuid: Uuid,
+    delivered_timestamp: number | null,
+    read_timestamp: number | null,
+}
 export interface UserGroupInfo {
     info: UserOut,
     clients: Uuid[],
@@ -57,6 +118,19 @@ export interface Group {
     name: string | null,
     image: number[] | null,
     members: { [key: Uuid]: UserGroupInfo },
+    out_of_sync: boolean,
+}
+export interface LocationSettings {
+    enabled: boolean,
+    paused_until: number | null,
+    precision: LocationPrecision,
+}
+export interface MemberLocation {
+    user_uuid: Uuid,
+    username: string | null,
+    name: string | null,
+    client_uuid: Uuid,
+    location: Location,
 }
 
 /* ---------------- FUNCTION DEFINITIONS ------------------- */
@@ -69,6 +143,46 @@ export function receive_messages(instance: FrontendInstance,): Promise<Result<nu
     })).then((res: string) => JSON.parse(res));
 }
 
+export function flush_outbox(instance: FrontendInstance,): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'flush_outbox',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_dropped_messages(instance: FrontendInstance,): Promise<Result<DroppedMessage[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_dropped_messages',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function dismiss_dropped_message(instance: FrontendInstance,id: number ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'dismiss_dropped_message',
+        args: {id},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function subscribe_to_messages(instance: FrontendInstance,): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'subscribe_to_messages',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function unsubscribe_from_messages(instance: FrontendInstance,): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'unsubscribe_from_messages',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function request_location_permissions(instance: FrontendInstance,): Promise<Result<boolean, void>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -101,6 +215,62 @@ export function unsubscribe_from_location_updates(instance: FrontendInstance,):
     })).then((res: string) => JSON.parse(res));
 }
 
+export function create_place(instance: FrontendInstance,group_uuid: Uuid , name: string , latitude: number , longitude: number , radius: number ): Promise<Result<Uuid, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'create_place',
+        args: {group_uuid, name, latitude, longitude, radius},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_places(instance: FrontendInstance,group_uuid: Uuid ): Promise<Result<Place[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_places',
+        args: {group_uuid},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function delete_place(instance: FrontendInstance,place_uuid: Uuid ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'delete_place',
+        args: {place_uuid},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_place_events(instance: FrontendInstance,group_uuid: Uuid , before_timestamp: number , amount: number ): Promise<Result<PlaceEvent[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_place_events',
+        args: {group_uuid, before_timestamp, amount},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function export_backup(instance: FrontendInstance,passphrase: string ): Promise<Result<Base64, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'export_backup',
+        args: {passphrase},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function restore_backup(instance: FrontendInstance,backup: Base64 , passphrase: string , username_or_email: string , password: string ): Promise<Result<Uuid, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'restore_backup',
+        args: {backup, passphrase, username_or_email, password},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function request_rejoin(instance: FrontendInstance,): Promise<Result<number, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'request_rejoin',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function replace_key_packages(instance: FrontendInstance,): Promise<Result<void, Error>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -109,6 +279,38 @@ export function replace_key_packages(instance: FrontendInstance,): Promise<Resul
     })).then((res: string) => JSON.parse(res));
 }
 
+export function register_push_token(instance: FrontendInstance,token: string ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'register_push_token',
+        args: {token},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function revoke_push_token(instance: FrontendInstance,): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'revoke_push_token',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function sync_clients(instance: FrontendInstance,): Promise<Result<number, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'sync_clients',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_timeline(instance: FrontendInstance,group_uuid: Uuid , client: Uuid , from_timestamp: number , to_timestamp: number ): Promise<Result<TimelineSegment[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_timeline',
+        args: {group_uuid, client, from_timestamp, to_timestamp},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function register(instance: FrontendInstance,username: string , password: string , name: string , email: string ): Promise<Result<void, Error>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -173,6 +375,30 @@ export function search(instance: FrontendInstance,query: string ): Promise<Resul
     })).then((res: string) => JSON.parse(res));
 }
 
+export function get_retention_settings(instance: FrontendInstance,): Promise<Result<RetentionSettings, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_retention_settings',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function set_retention_settings(instance: FrontendInstance,settings: RetentionSettings ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'set_retention_settings',
+        args: {settings},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function compact_locations(instance: FrontendInstance,): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'compact_locations',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function status(instance: FrontendInstance,): Promise<Result<Status, void>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -181,6 +407,78 @@ export function status(instance: FrontendInstance,): Promise<Result<Status, void
     })).then((res: string) => JSON.parse(res));
 }
 
+export function send_text(instance: FrontendInstance,group_uuid: Uuid , body: string , reply_to: Uuid | null ): Promise<Result<Uuid, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'send_text',
+        args: {group_uuid, body, reply_to},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_messages(instance: FrontendInstance,group_uuid: Uuid , before_timestamp: number , amount: number ): Promise<Result<ChatMessage[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_messages',
+        args: {group_uuid, before_timestamp, amount},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_receipt_settings(instance: FrontendInstance,): Promise<Result<ReceiptSettings, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_receipt_settings',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function set_receipt_settings(instance: FrontendInstance,settings: ReceiptSettings ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'set_receipt_settings',
+        args: {settings},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function mark_messages_read(instance: FrontendInstance,group_uuid: Uuid , message_uuids: Uuid[] ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'mark_messages_read',
+        args: {group_uuid, message_uuids},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_receipts(instance: FrontendInstance,group_uuid: Uuid , message_uuid: Uuid ): Promise<Result<MessageReceipt[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_receipts',
+        args: {group_uuid, message_uuid},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function start_device_link(instance: FrontendInstance,username_or_email: string , password: string ): Promise<Result<string, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'start_device_link',
+        args: {username_or_email, password},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function finish_device_link(instance: FrontendInstance,): Promise<Result<Uuid | null, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'finish_device_link',
+        args: {},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function link_device(instance: FrontendInstance,code: string ): Promise<Result<boolean, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'link_device',
+        args: {code},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function get_groups(instance: FrontendInstance,): Promise<Result<Group[], Error>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -221,7 +519,7 @@ export function leave_group(instance: FrontendInstance,group_uuid: Uuid ): Promi
     })).then((res: string) => JSON.parse(res));
 }
 
-export function update_group(instance: FrontendInstance,group_uuid: Uuid , name: string | null ): Promise<Result<void, Error>> {
+export function update_group(instance: FrontendInstance,group_uuid: Uuid , name: string | null ): Promise<Result<Uuid, Error>> {
     return RustInterop.call(JSON.stringify({
         instance,
         method: 'update_group',
@@ -229,7 +527,7 @@ export function update_group(instance: FrontendInstance,group_uuid: Uuid , name:
     })).then((res: string) => JSON.parse(res));
 }
 
-export function send_group_status(instance: FrontendInstance,group_uuid: Uuid ): Promise<Result<void, Error>> {
+export function send_group_status(instance: FrontendInstance,group_uuid: Uuid ): Promise<Result<Uuid, Error>> {
     return RustInterop.call(JSON.stringify({
         instance,
         method: 'send_group_status',
@@ -237,6 +535,30 @@ export function send_group_status(instance: FrontendInstance,group_uuid: Uuid ):
     })).then((res: string) => JSON.parse(res));
 }
 
+export function set_group_image(instance: FrontendInstance,group_uuid: Uuid , image: Base64 | null ): Promise<Result<Uuid, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'set_group_image',
+        args: {group_uuid, image},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function get_location_settings(instance: FrontendInstance,group_uuid: Uuid ): Promise<Result<LocationSettings, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_location_settings',
+        args: {group_uuid},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function set_location_settings(instance: FrontendInstance,group_uuid: Uuid , settings: LocationSettings ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'set_location_settings',
+        args: {group_uuid, settings},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function get_location(instance: FrontendInstance,group_uuid: Uuid , client: Uuid , before_timestamp: number , amount: number ): Promise<Result<Location[], void>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -245,6 +567,14 @@ export function get_location(instance: FrontendInstance,group_uuid: Uuid , clien
     })).then((res: string) => JSON.parse(res));
 }
 
+export function get_latest_locations(instance: FrontendInstance,group_uuid: Uuid ): Promise<Result<MemberLocation[], Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'get_latest_locations',
+        args: {group_uuid},
+    })).then((res: string) => JSON.parse(res));
+}
+
 export function get_num_location(instance: FrontendInstance,group_uuid: Uuid , client: Uuid , from_timestamp: number , to_timestamp: number ): Promise<Result<number, void>> {
     return RustInterop.call(JSON.stringify({
         instance,
@@ -253,11 +583,35 @@ export function get_num_location(instance: FrontendInstance,group_uuid: Uuid , c
     })).then((res: string) => JSON.parse(res));
 }
 
-export function send_location(instance: FrontendInstance,group_uuid: Uuid , longitude: number , latitude: number , timestamp: number ): Promise<Result<void, void>> {
+export function export_locations(instance: FrontendInstance,group_uuid: Uuid , client: Uuid , from_timestamp: number , to_timestamp: number , format: string ): Promise<Result<string, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'export_locations',
+        args: {group_uuid, client, from_timestamp, to_timestamp, format},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function send_location(instance: FrontendInstance,group_uuid: Uuid , location: Location ): Promise<Result<Uuid | null, void>> {
     return RustInterop.call(JSON.stringify({
         instance,
         method: 'send_location',
-        args: {group_uuid, longitude, latitude, timestamp},
+        args: {group_uuid, location},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function send_locations(instance: FrontendInstance,group_uuid: Uuid , locations: Location[] ): Promise<Result<Uuid | null, void>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'send_locations',
+        args: {group_uuid, locations},
+    })).then((res: string) => JSON.parse(res));
+}
+
+export function request_member_location(instance: FrontendInstance,group_uuid: Uuid , user_uuid: Uuid | null ): Promise<Result<void, Error>> {
+    return RustInterop.call(JSON.stringify({
+        instance,
+        method: 'request_member_location',
+        args: {group_uuid, user_uuid},
     })).then((res: string) => JSON.parse(res));
 }
 
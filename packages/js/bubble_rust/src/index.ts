@@ -15,6 +15,12 @@ export type Result<T, E> =
 export type Uuid = string;
 export type Base64 = string;
 
+// bridge_gen only generates structs, see application_message::LocationPrecision
+export type LocationPrecision =
+    | { mode: 'exact' }
+    | { mode: 'grid'; metres: number }
+    | { mode: 'city' };
+
 export function init(
     options: InitOptions
 ): Promise<Result<FrontendInstance, string>> {
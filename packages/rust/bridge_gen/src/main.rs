@@ -28,7 +28,7 @@ fn main() {
     let mut output = r#"/* WARNING: This file is auto-generated. Do not modify. */
 
 import { NativeModules, Platform } from 'react-native';
-import { Result, Uuid, FrontendInstance, Base64 } from './index';
+import { Result, Uuid, FrontendInstance, Base64, LocationPrecision } from './index';
 
 const LINKING_ERROR =
     `The package 'react-native-bubble-rust' doesn't seem to be linked. Make sure: \n\n${Platform.select(
@@ -12,6 +12,15 @@ pub fn serialize(data: &[u8]) -> String {
     general_purpose::STANDARD.encode(data)
 }
 
+// for binary data in paths, e.g. the hash of a blob
+pub fn serialize_url(data: &[u8]) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(data)
+}
+
+pub fn deserialize_url(data: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    general_purpose::URL_SAFE_NO_PAD.decode(data)
+}
+
 // https://users.rust-lang.org/t/serialize-a-vec-u8-to-json-as-base64/57781/5
 #[derive(Clone, Debug)]
 pub struct Base64(pub Vec<u8>);
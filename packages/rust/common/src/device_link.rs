@@ -0,0 +1,12 @@
+use sha2::{Digest, Sha256};
+
+// digits, guessing a key with the same code has to be impractical
+pub const CODE_LENGTH: u32 = 12;
+
+// the code is derived from the new device's public key, so the device that enters it can tell whether the server
+// handed it the key the new device generated
+pub fn code(public_key: &[u8]) -> String {
+    let hash = Sha256::digest(public_key);
+    let number = u64::from_be_bytes(hash[..8].try_into().unwrap()) % 10u64.pow(CODE_LENGTH);
+    format!("{:0width$}", number, width = CODE_LENGTH as usize)
+}
@@ -29,6 +29,11 @@ pub struct KeyPackagePublic {
     pub key_package: Base64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct RegisterPushToken {
+    pub token: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Message {
     pub message: Base64,
@@ -38,22 +43,126 @@ pub struct Message {
 pub struct SendMessage {
     pub client_uuids: Vec<Uuid>,
     pub message: Message,
+    pub group_uuid: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SendGroupMessage {
+    // the sending client, it has to be a member of the group
+    pub client_uuid: Uuid,
+    pub message: Message,
+    pub exclude_client_uuids: Vec<Uuid>,
+    // only sent with a commit, the group's clients once it's merged
+    // the server fans messages out to them if the commit wins its epoch
+    pub members: Option<Vec<Uuid>>,
+}
+
+// returned with a 409 when another commit was already accepted for the epoch
+#[derive(Serialize, Deserialize)]
+pub struct CommitConflict {
+    pub commit: Base64,
+}
+
+// an encrypted attachment, the server never sees its key
+#[derive(Serialize, Deserialize)]
+pub struct Blob {
+    pub data: Base64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UploadBlob {
+    pub data: Base64,
+    // the blob is deleted once all of them downloaded it, it's only deleted when it expires if this is empty
+    pub client_uuids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UploadBlobResponse {
+    // sha256 of the data, the blob is downloaded with it
+    pub hash: Base64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DownloadBlob {
+    // the recipient downloading the blob
+    pub client_uuid: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateDeviceLink {
+    // the new device's hpke public key, the identity key is encrypted to it
+    pub public_key: Base64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateDeviceLinkResponse {
+    // shown on the new device and entered on a logged in one
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceLink {
+    pub public_key: Base64,
+    // set by the logged in device
+    pub encrypted_key: Option<Base64>,
+    // set by the new device once it created its client
+    pub client_uuid: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateDeviceLink {
+    pub encrypted_key: Option<Base64>,
+    pub client_uuid: Option<Uuid>,
+}
+
+// the group starts out with the creating client and `members`
+#[derive(Serialize, Deserialize)]
+pub struct CreateGroup {
+    pub client_uuid: Uuid,
+    // the group's other clients, only a group that existed before the server knew about groups has any
+    #[serde(default)]
+    pub members: Vec<Uuid>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CheckMessages {
     pub client_uuid: Uuid,
+    // the server's default is used when None
+    pub limit: Option<u32>,
+    // id of the last message of the previous page
+    pub cursor: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AckMessages {
+    pub client_uuid: Uuid,
+    pub message_ids: Vec<i32>,
+    pub expired_ids: Vec<i32>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct DeliveredMessage {
+    pub id: i32,
+    pub group_uuid: Option<Uuid>,
     pub message: Base64,
     pub received_date: i64,
 }
 
+// messages of a group that expired before they were fetched
+#[derive(Serialize, Deserialize)]
+pub struct ExpiredMessages {
+    pub id: i32,
+    pub group_uuid: Option<Uuid>,
+    pub amount: i32,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct MessagesResponse {
     pub messages: Vec<DeliveredMessage>,
+    pub has_more: bool,
+    pub next_cursor: Option<i32>,
+    // only returned with the first page
+    pub expired: Vec<ExpiredMessages>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -80,7 +189,7 @@ pub struct SessionTokenRequest {
     pub token: Uuid,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct SessionTokenResponse {
     pub user_uuid: Uuid,
     pub bearer: Uuid,
@@ -1,2 +1,3 @@
 pub mod base64;
+pub mod device_link;
 pub mod http_types;
@@ -0,0 +1,6 @@
+ALTER TABLE
+    message DROP COLUMN group_id;
+
+DROP TABLE group_member;
+
+DROP TABLE "group";
\ No newline at end of file
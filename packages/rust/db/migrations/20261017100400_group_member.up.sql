@@ -0,0 +1,20 @@
+CREATE TABLE "group" (
+    id SERIAL PRIMARY KEY,
+    uuid UUID UNIQUE NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE group_member (
+    id SERIAL PRIMARY KEY,
+    group_id INT REFERENCES "group" (id) ON DELETE CASCADE NOT NULL,
+    client_id INT REFERENCES client (id) ON DELETE CASCADE NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (group_id, client_id)
+);
+
+ALTER TABLE
+    message
+ADD
+    COLUMN group_id INT REFERENCES "group" (id) ON DELETE
+SET
+    NULL NULL;
\ No newline at end of file
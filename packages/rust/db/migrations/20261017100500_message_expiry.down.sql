@@ -0,0 +1,4 @@
+DROP TABLE expired_message;
+
+ALTER TABLE
+    message DROP COLUMN expires;
\ No newline at end of file
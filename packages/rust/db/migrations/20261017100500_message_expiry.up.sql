@@ -0,0 +1,25 @@
+-- messages from before expiry was added get the default ttl of 30 days
+ALTER TABLE
+    message
+ADD
+    COLUMN expires TIMESTAMP NULL;
+
+UPDATE
+    message
+SET
+    expires = COALESCE(created, CURRENT_TIMESTAMP) + INTERVAL '30 days';
+
+ALTER TABLE
+    message
+ALTER COLUMN
+    expires
+SET
+    NOT NULL;
+
+CREATE TABLE expired_message (
+    id SERIAL PRIMARY KEY,
+    client_id INT REFERENCES client (id) ON DELETE CASCADE NOT NULL,
+    group_id INT REFERENCES "group" (id) ON DELETE CASCADE NULL,
+    amount INT NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE group_commit;
\ No newline at end of file
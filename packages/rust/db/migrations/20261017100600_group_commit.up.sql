@@ -0,0 +1,8 @@
+CREATE TABLE group_commit (
+    id SERIAL PRIMARY KEY,
+    group_id INT REFERENCES "group" (id) ON DELETE CASCADE NOT NULL,
+    epoch BIGINT NOT NULL,
+    commit BYTEA NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (group_id, epoch)
+);
\ No newline at end of file
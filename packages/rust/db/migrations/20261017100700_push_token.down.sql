@@ -0,0 +1 @@
+DROP TABLE push_token;
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE push_token (
+    id SERIAL PRIMARY KEY,
+    client_id INT REFERENCES client (id) ON DELETE CASCADE UNIQUE NOT NULL,
+    token TEXT NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
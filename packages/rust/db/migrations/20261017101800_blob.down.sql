@@ -0,0 +1 @@
+DROP TABLE blob;
\ No newline at end of file
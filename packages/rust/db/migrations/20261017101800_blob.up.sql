@@ -0,0 +1,7 @@
+CREATE TABLE blob (
+    id SERIAL PRIMARY KEY,
+    hash BYTEA UNIQUE NOT NULL,
+    data BYTEA NOT NULL,
+    user_id INT REFERENCES "user" (id) ON DELETE CASCADE NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1,4 @@
+DROP TABLE blob_recipient;
+
+ALTER TABLE
+    blob DROP COLUMN expires;
\ No newline at end of file
@@ -0,0 +1,25 @@
+-- blobs from before expiry was added get the default ttl of 30 days
+ALTER TABLE
+    blob
+ADD
+    COLUMN expires TIMESTAMP NULL;
+
+UPDATE
+    blob
+SET
+    expires = COALESCE(created, CURRENT_TIMESTAMP) + INTERVAL '30 days';
+
+ALTER TABLE
+    blob
+ALTER COLUMN
+    expires
+SET
+    NOT NULL;
+
+CREATE TABLE blob_recipient (
+    id SERIAL PRIMARY KEY,
+    client_id INT REFERENCES client (id) ON DELETE CASCADE NOT NULL,
+    blob_id INT REFERENCES blob (id) ON DELETE CASCADE NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (client_id, blob_id)
+);
\ No newline at end of file
@@ -0,0 +1,11 @@
+CREATE TABLE device_link (
+    id SERIAL PRIMARY KEY,
+    user_id INT REFERENCES "user" (id) ON DELETE CASCADE NOT NULL,
+    code TEXT NOT NULL,
+    public_key BYTEA NOT NULL,
+    encrypted_key BYTEA NULL,
+    client_id INT REFERENCES client (id) ON DELETE CASCADE NULL,
+    expires TIMESTAMP NOT NULL,
+    created TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (user_id, code)
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP INDEX inbox_server_id;
+
+ALTER TABLE inbox DROP COLUMN server_id
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- messages already in the inbox were fetched before acknowledgements, they get ids the server never uses
+ALTER TABLE inbox ADD COLUMN server_id INTEGER NOT NULL DEFAULT 0;
+
+UPDATE inbox SET server_id = -id;
+
+CREATE UNIQUE INDEX inbox_server_id ON inbox (server_id)
\ No newline at end of file
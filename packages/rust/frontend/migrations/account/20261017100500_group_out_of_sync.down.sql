@@ -0,0 +1 @@
+ALTER TABLE "group" DROP COLUMN out_of_sync
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE "group" ADD COLUMN out_of_sync BOOLEAN NOT NULL DEFAULT FALSE
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE outbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    group_uuid TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    message BLOB NOT NULL,
+    client_uuids TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_date DATETIME NOT NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
\ No newline at end of file
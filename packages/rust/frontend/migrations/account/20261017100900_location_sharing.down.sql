@@ -0,0 +1,5 @@
+ALTER TABLE "group" DROP COLUMN location_precision;
+
+ALTER TABLE "group" DROP COLUMN location_paused_until;
+
+ALTER TABLE "group" DROP COLUMN location_sharing
\ No newline at end of file
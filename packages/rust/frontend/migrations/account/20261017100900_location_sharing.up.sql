@@ -0,0 +1,5 @@
+ALTER TABLE "group" ADD COLUMN location_sharing BOOLEAN NOT NULL DEFAULT TRUE;
+
+ALTER TABLE "group" ADD COLUMN location_paused_until DATETIME NULL;
+
+ALTER TABLE "group" ADD COLUMN location_precision INTEGER NULL
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE location DROP COLUMN precision
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE location ADD COLUMN precision INTEGER NULL
\ No newline at end of file
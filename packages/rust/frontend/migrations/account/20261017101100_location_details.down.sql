@@ -0,0 +1,15 @@
+ALTER TABLE location DROP COLUMN speed_accuracy;
+
+ALTER TABLE location DROP COLUMN course_accuracy;
+
+ALTER TABLE location DROP COLUMN vertical_accuracy;
+
+ALTER TABLE location DROP COLUMN horizontal_accuracy;
+
+ALTER TABLE location DROP COLUMN speed;
+
+ALTER TABLE location DROP COLUMN course;
+
+ALTER TABLE location DROP COLUMN floor;
+
+ALTER TABLE location DROP COLUMN altitude
\ No newline at end of file
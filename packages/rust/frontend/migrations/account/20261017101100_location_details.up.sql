@@ -0,0 +1,15 @@
+ALTER TABLE location ADD COLUMN altitude REAL NULL;
+
+ALTER TABLE location ADD COLUMN floor INTEGER NULL;
+
+ALTER TABLE location ADD COLUMN course REAL NULL;
+
+ALTER TABLE location ADD COLUMN speed REAL NULL;
+
+ALTER TABLE location ADD COLUMN horizontal_accuracy REAL NULL;
+
+ALTER TABLE location ADD COLUMN vertical_accuracy REAL NULL;
+
+ALTER TABLE location ADD COLUMN course_accuracy REAL NULL;
+
+ALTER TABLE location ADD COLUMN speed_accuracy REAL NULL
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP TABLE place_event;
+
+DROP TABLE place
\ No newline at end of file
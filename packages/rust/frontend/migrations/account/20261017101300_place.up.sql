@@ -0,0 +1,20 @@
+CREATE TABLE place (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    uuid TEXT NOT NULL UNIQUE,
+    group_uuid TEXT NOT NULL,
+    name TEXT NOT NULL,
+    latitude REAL NOT NULL,
+    longitude REAL NOT NULL,
+    radius REAL NOT NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE place_event (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    place_uuid TEXT NOT NULL,
+    group_uuid TEXT NOT NULL,
+    client_uuid TEXT NOT NULL,
+    entered BOOLEAN NOT NULL,
+    event_date INTEGER NOT NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
\ No newline at end of file
@@ -0,0 +1,27 @@
+-- sqlite can't drop a NOT NULL constraint, so the table is rebuilt
+CREATE TABLE location_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    client_uuid TEXT NOT NULL,
+    group_uuid TEXT NOT NULL,
+    longitude REAL NOT NULL,
+    latitude REAL NOT NULL,
+    location_date INTEGER NOT NULL,
+    precision INTEGER NULL,
+    altitude REAL NULL,
+    floor INTEGER NULL,
+    course REAL NULL,
+    speed REAL NULL,
+    horizontal_accuracy REAL NULL,
+    vertical_accuracy REAL NULL,
+    course_accuracy REAL NULL,
+    speed_accuracy REAL NULL,
+    raw BLOB NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO location_new (id, client_uuid, group_uuid, longitude, latitude, location_date, precision, altitude, floor, course, speed, horizontal_accuracy, vertical_accuracy, course_accuracy, speed_accuracy, raw, created_date)
+SELECT id, client_uuid, group_uuid, longitude, latitude, location_date, precision, altitude, floor, course, speed, horizontal_accuracy, vertical_accuracy, course_accuracy, speed_accuracy, raw, created_date FROM location;
+
+DROP TABLE location;
+
+ALTER TABLE location_new RENAME TO location
\ No newline at end of file
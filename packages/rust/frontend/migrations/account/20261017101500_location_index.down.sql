@@ -0,0 +1 @@
+DROP INDEX location_group_client_date
\ No newline at end of file
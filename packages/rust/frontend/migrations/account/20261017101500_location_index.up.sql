@@ -0,0 +1 @@
+CREATE INDEX location_group_client_date ON location (group_uuid, client_uuid, location_date)
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE location_segment
\ No newline at end of file
@@ -0,0 +1,14 @@
+CREATE TABLE location_segment (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    group_uuid TEXT NOT NULL,
+    client_uuid TEXT NOT NULL,
+    stay BOOLEAN NOT NULL,
+    start_date INTEGER NOT NULL,
+    end_date INTEGER NOT NULL,
+    latitude REAL NULL,
+    longitude REAL NULL,
+    distance REAL NOT NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX location_segment_group_client_date ON location_segment (group_uuid, client_uuid, start_date)
\ No newline at end of file
@@ -0,0 +1,13 @@
+CREATE TABLE chat_message (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    uuid TEXT NOT NULL,
+    group_uuid TEXT NOT NULL,
+    client_uuid TEXT NOT NULL,
+    body TEXT NOT NULL,
+    reply_to TEXT NULL,
+    message_date INTEGER NOT NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (group_uuid, uuid)
+);
+
+CREATE INDEX chat_message_group_date ON chat_message (group_uuid, message_date)
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE receipt
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TABLE receipt (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    group_uuid TEXT NOT NULL,
+    message_uuid TEXT NOT NULL,
+    client_uuid TEXT NOT NULL,
+    delivered_date INTEGER NOT NULL,
+    read_date INTEGER NULL,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (group_uuid, message_uuid, client_uuid)
+)
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE "group" DROP COLUMN location_request_answered_at
\ No newline at end of file
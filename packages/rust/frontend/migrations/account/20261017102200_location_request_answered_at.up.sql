@@ -0,0 +1 @@
+ALTER TABLE "group" ADD COLUMN location_request_answered_at DATETIME NULL
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- city level locations keep their grid size and become grids
+ALTER TABLE location DROP COLUMN precision_mode;
+
+ALTER TABLE "group" DROP COLUMN location_precision_mode
\ No newline at end of file
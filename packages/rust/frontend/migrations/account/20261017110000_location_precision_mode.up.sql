@@ -0,0 +1,7 @@
+ALTER TABLE "group" ADD COLUMN location_precision_mode TEXT NOT NULL DEFAULT 'exact';
+
+UPDATE "group" SET location_precision_mode = 'grid' WHERE location_precision IS NOT NULL;
+
+ALTER TABLE location ADD COLUMN precision_mode TEXT NOT NULL DEFAULT 'exact';
+
+UPDATE location SET precision_mode = 'grid' WHERE precision IS NOT NULL
\ No newline at end of file
@@ -0,0 +1,7 @@
+ALTER TABLE "group" DROP COLUMN image_attachment_clients;
+
+ALTER TABLE "group" DROP COLUMN image_attachment_nonce;
+
+ALTER TABLE "group" DROP COLUMN image_attachment_key;
+
+ALTER TABLE "group" DROP COLUMN image_attachment_hash
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- the attachment the group image was last sent or received with, the clients are only known if we uploaded it
+ALTER TABLE "group" ADD COLUMN image_attachment_hash BLOB NULL;
+
+ALTER TABLE "group" ADD COLUMN image_attachment_key BLOB NULL;
+
+ALTER TABLE "group" ADD COLUMN image_attachment_nonce BLOB NULL;
+
+ALTER TABLE "group" ADD COLUMN image_attachment_clients TEXT NULL
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE outbox DROP COLUMN error;
+
+ALTER TABLE outbox DROP COLUMN dropped_date
\ No newline at end of file
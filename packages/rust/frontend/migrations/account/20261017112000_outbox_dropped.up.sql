@@ -0,0 +1,4 @@
+-- a message that failed permanently or too many times, it no longer holds up the messages after it
+ALTER TABLE outbox ADD COLUMN dropped_date DATETIME NULL;
+
+ALTER TABLE outbox ADD COLUMN error TEXT NULL
\ No newline at end of file
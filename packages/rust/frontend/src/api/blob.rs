@@ -0,0 +1,47 @@
+use crate::api::BubbleApi;
+use common::base64::{serialize_url, Base64};
+use common::http_types::{Blob, DownloadBlob, UploadBlob, UploadBlobResponse};
+use uuid::Uuid;
+
+impl BubbleApi {
+    // returns the hash the blob can be downloaded with
+    pub async fn upload_blob(
+        &self,
+        data: Vec<u8>,
+        client_uuids: Vec<Uuid>,
+    ) -> Result<Vec<u8>, reqwest::Error> {
+        let response: UploadBlobResponse = self
+            .client
+            .post(format!("{}/v1/blob", self.domain))
+            .json(&UploadBlob {
+                data: Base64(data),
+                client_uuids,
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.hash.0)
+    }
+
+    // the blob is deleted once every recipient downloaded it
+    pub async fn download_blob(
+        &self,
+        hash: &[u8],
+        client_uuid: Uuid,
+    ) -> Result<Vec<u8>, reqwest::Error> {
+        let blob: Blob = self
+            .client
+            .get(format!("{}/v1/blob/{}", self.domain, serialize_url(hash)))
+            .query(&DownloadBlob {
+                client_uuid: Some(client_uuid),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(blob.data.0)
+    }
+}
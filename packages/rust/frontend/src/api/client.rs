@@ -1,7 +1,8 @@
 use crate::api::BubbleApi;
 use common::base64::Base64;
 use common::http_types::{
-    CreateClient, CreateClientResponse, KeyPackagePublic, PublicClient, ReplaceKeyPackages,
+    CreateClient, CreateClientResponse, KeyPackagePublic, PublicClient, RegisterPushToken,
+    ReplaceKeyPackages,
 };
 use openmls::prelude::{KeyPackage, KeyPackageIn};
 use tls_codec::{Deserialize, Serialize};
@@ -82,4 +83,42 @@ impl BubbleApi {
             .await?;
         Ok(res.client_uuid)
     }
+
+    pub async fn delete_client(&self, client_uuid: &Uuid) -> Result<(), reqwest::Error> {
+        self.client
+            .delete(format!("{}/v1/client/{}", self.domain, client_uuid))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn register_push_token(
+        &self,
+        client_uuid: &Uuid,
+        token: String,
+    ) -> Result<(), reqwest::Error> {
+        self.client
+            .put(format!(
+                "{}/v1/client/{}/push_token",
+                self.domain, client_uuid
+            ))
+            .json(&RegisterPushToken { token })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn revoke_push_token(&self, client_uuid: &Uuid) -> Result<(), reqwest::Error> {
+        self.client
+            .delete(format!(
+                "{}/v1/client/{}/push_token",
+                self.domain, client_uuid
+            ))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,61 @@
+use crate::api::BubbleApi;
+use common::base64::Base64;
+use common::http_types::{
+    CreateDeviceLink, CreateDeviceLinkResponse, DeviceLink, UpdateDeviceLink,
+};
+use uuid::Uuid;
+
+impl BubbleApi {
+    // returns the code the logged in device enters
+    pub async fn create_device_link(&self, public_key: Vec<u8>) -> Result<String, reqwest::Error> {
+        let response: CreateDeviceLinkResponse = self
+            .client
+            .post(format!("{}/v1/device_link", self.domain))
+            .json(&CreateDeviceLink {
+                public_key: Base64(public_key),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.code)
+    }
+
+    pub async fn get_device_link(&self, code: &str) -> Result<DeviceLink, reqwest::Error> {
+        self.client
+            .get(format!("{}/v1/device_link/{}", self.domain, code))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    pub async fn update_device_link(
+        &self,
+        code: &str,
+        encrypted_key: Option<Vec<u8>>,
+        client_uuid: Option<Uuid>,
+    ) -> Result<(), reqwest::Error> {
+        self.client
+            .patch(format!("{}/v1/device_link/{}", self.domain, code))
+            .json(&UpdateDeviceLink {
+                encrypted_key: encrypted_key.map(Base64),
+                client_uuid,
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn delete_device_link(&self, code: &str) -> Result<(), reqwest::Error> {
+        self.client
+            .delete(format!("{}/v1/device_link/{}", self.domain, code))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::api::BubbleApi;
+use common::base64::Base64;
+use common::http_types::{CommitConflict, CreateGroup, Message, SendGroupMessage};
+use reqwest::StatusCode;
+use uuid::Uuid;
+
+impl BubbleApi {
+    pub async fn create_group(
+        &self,
+        group_uuid: Uuid,
+        client_uuid: Uuid,
+        members: Vec<Uuid>,
+    ) -> Result<(), reqwest::Error> {
+        self.client
+            .post(format!("{}/v1/group/{}", self.domain, group_uuid))
+            .json(&CreateGroup {
+                client_uuid,
+                members,
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    // returns the winning commit if the server already accepted another commit for the epoch
+    pub async fn send_group_message(
+        &self,
+        client_uuid: Uuid,
+        group_uuid: Uuid,
+        message: Vec<u8>,
+        exclude_client_uuids: Vec<Uuid>,
+        members: Option<Vec<Uuid>>,
+    ) -> Result<Option<Vec<u8>>, reqwest::Error> {
+        let response = self
+            .client
+            .post(format!("{}/v1/group/{}/message", self.domain, group_uuid))
+            .json(&SendGroupMessage {
+                client_uuid,
+                message: Message {
+                    message: Base64(message),
+                },
+                exclude_client_uuids,
+                members,
+            })
+            .send()
+            .await?;
+        if response.status() == StatusCode::CONFLICT {
+            let conflict: CommitConflict = response.json().await?;
+            return Ok(Some(conflict.commit.0));
+        }
+        response.error_for_status()?;
+        Ok(None)
+    }
+}
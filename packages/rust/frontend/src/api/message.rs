@@ -1,14 +1,51 @@
 use crate::api::BubbleApi;
 use common::base64::Base64;
-use common::http_types::{CheckMessages, DeliveredMessage, Message, MessagesResponse, SendMessage};
+use common::http_types::{
+    AckMessages, CheckMessages, DeliveredMessage, Message, MessagesResponse, SendMessage,
+};
+use log::warn;
 use uuid::Uuid;
 
+/// A server-sent event stream of messages delivered to a client as soon as they are sent.
+pub struct MessageStream {
+    response: reqwest::Response,
+    buffer: String,
+}
+
+impl MessageStream {
+    /// Returns the next message, or None once the server closes the stream.
+    pub async fn next(&mut self) -> Result<Option<DeliveredMessage>, reqwest::Error> {
+        loop {
+            while let Some(end) = self.buffer.find("\n\n") {
+                let event: String = self.buffer.drain(..end + 2).collect();
+                let data: String = event
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data:"))
+                    .map(|data| data.trim_start())
+                    .collect();
+                if data.is_empty() {
+                    // keep alive comment
+                    continue;
+                }
+                match serde_json::from_str(&data) {
+                    Ok(message) => return Ok(Some(message)),
+                    Err(e) => warn!("unable to parse streamed message: {}", e),
+                }
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.push_str(&String::from_utf8_lossy(&chunk)),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
 impl BubbleApi {
     pub async fn send_message(
         &self,
         client_uuids: Vec<Uuid>,
         message: Vec<u8>,
-        _group_uuid: Uuid,
+        group_uuid: Uuid,
     ) -> Result<(), reqwest::Error> {
         if client_uuids.is_empty() {
             return Ok(());
@@ -19,29 +56,76 @@ impl BubbleApi {
         let message = SendMessage {
             client_uuids,
             message,
+            group_uuid: Some(group_uuid),
         };
         self.client
             .post(&format!("{}/v1/message", self.domain))
             .json(&message)
             .send()
-            .await?;
+            .await?
+            .error_for_status()?;
         Ok(())
     }
 
     pub async fn receive_messages(
         &self,
         client_uuid: Uuid,
-    ) -> Result<Vec<DeliveredMessage>, reqwest::Error> {
+        cursor: Option<i32>,
+    ) -> Result<MessagesResponse, reqwest::Error> {
         let response: MessagesResponse = self
             .client
             .get(&format!("{}/v1/message", self.domain))
-            .json(&CheckMessages { client_uuid })
+            .json(&CheckMessages {
+                client_uuid,
+                limit: None,
+                cursor,
+            })
             .send()
             .await?
             .error_for_status()?
             .json()
             .await
             .unwrap();
-        Ok(response.messages)
+        Ok(response)
+    }
+
+    pub async fn ack_messages(
+        &self,
+        client_uuid: Uuid,
+        message_ids: Vec<i32>,
+        expired_ids: Vec<i32>,
+    ) -> Result<(), reqwest::Error> {
+        self.client
+            .post(format!("{}/v1/message/ack", self.domain))
+            .json(&AckMessages {
+                client_uuid,
+                message_ids,
+                expired_ids,
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub async fn stream_messages(
+        &self,
+        client_uuid: Uuid,
+    ) -> Result<MessageStream, reqwest::Error> {
+        let response = self
+            .client
+            .get(format!("{}/v1/message/stream", self.domain))
+            .json(&CheckMessages {
+                client_uuid,
+                limit: None,
+                cursor: None,
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(MessageStream {
+            response,
+            buffer: String::new(),
+        })
     }
 }
@@ -1,4 +1,7 @@
+mod blob;
 mod client;
+mod device_link;
+mod group;
 mod message;
 mod user;
 
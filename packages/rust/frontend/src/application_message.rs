@@ -1,25 +1,128 @@
 use bridge_macro::bridge;
 use common::base64::Base64;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum Message {
-    Location(Location),
+    Location(LocationUpdate),
+    LocationBatch(LocationBatch),
     GroupStatus(GroupStatus),
+    Text(Text),
+    Receipt(Receipt),
+    LocationRequest(LocationRequest),
+    // a type added by a newer client, it's skipped
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+// the optional fields are missing from the messages of older clients
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 #[bridge]
 pub struct Location {
     pub latitude: f64,
     pub longitude: f64,
     pub timestamp: i64,
+    // how much the sender coarsened the location
+    #[serde(default)]
+    pub precision: LocationPrecision,
+    // metres
+    #[serde(default)]
+    pub altitude: Option<f64>,
+    #[serde(default)]
+    pub floor: Option<i32>,
+    // degrees clockwise from north
+    #[serde(default)]
+    pub course: Option<f64>,
+    // metres per second
+    #[serde(default)]
+    pub speed: Option<f64>,
+    #[serde(default)]
+    pub horizontal_accuracy: Option<f64>,
+    #[serde(default)]
+    pub vertical_accuracy: Option<f64>,
+    #[serde(default)]
+    pub course_accuracy: Option<f64>,
+    #[serde(default)]
+    pub speed_accuracy: Option<f64>,
+}
+
+// a single location, the id is generated by the sender so members can acknowledge it
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocationUpdate {
+    // missing from the messages of older clients
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    #[serde(flatten)]
+    pub location: Location,
+}
+
+// how much our locations are coarsened before they're sent to a group, the applied precision is sent with them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum LocationPrecision {
+    #[default]
+    Exact,
+    // snapped to a grid of this many metres
+    Grid {
+        metres: u32,
+    },
+    // snapped to a grid about the size of a city, receivers show the city rather than a point
+    City,
+}
+
+// locations sent together to save encrypting and sending each of them, e.g. the updates collected in the background
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocationBatch {
+    // acknowledged once for the whole batch
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub locations: Vec<Location>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GroupStatus {
+    #[serde(default)]
+    pub id: Option<Uuid>,
     pub name: Option<String>,
+    // small images are sent inline, larger ones as an attachment
     pub image: Option<Base64>,
+    #[serde(default)]
+    pub image_attachment: Option<Attachment>,
+}
+
+// a chat message, the id is generated by the sender so replies can refer to it
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Text {
+    pub id: Uuid,
+    pub body: String,
+    pub reply_to: Option<Uuid>,
+    pub timestamp: i64,
+}
+
+// acknowledges messages that have an id, sent to the whole group
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Receipt {
+    pub ids: Vec<Uuid>,
+    // false if the messages were only delivered
+    pub read: bool,
+    pub timestamp: i64,
+}
+
+// asks a member for a fresh location, they reply with a normal location message
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocationRequest {
+    // everyone in the group if None
+    pub user_uuid: Option<Uuid>,
+}
+
+// a file encrypted with its own key and uploaded to the server, see helper::attachment
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Attachment {
+    // sha256 of the encrypted file, the server knows it by this
+    pub hash: Base64,
+    pub key: Base64,
+    pub nonce: Base64,
 }
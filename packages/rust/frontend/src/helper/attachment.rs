@@ -0,0 +1,51 @@
+use crate::api::BubbleApi;
+use crate::application_message::Attachment;
+use crate::Error;
+use common::base64::Base64;
+use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::random::OpenMlsRand;
+use openmls_traits::types::{AeadType, CryptoError};
+use openmls_traits::OpenMlsCryptoProvider;
+use uuid::Uuid;
+
+const AEAD: AeadType = AeadType::ChaCha20Poly1305;
+
+// encrypts the data with a new key and uploads it for the clients, the key is only sent inside the group
+pub async fn upload_attachment(
+    api: &BubbleApi,
+    provider: &impl OpenMlsCryptoProvider,
+    data: &[u8],
+    client_uuids: Vec<Uuid>,
+) -> Result<Attachment, Error> {
+    let key = provider
+        .rand()
+        .random_vec(AEAD.key_size())
+        .map_err(|_| CryptoError::InsufficientRandomness)?;
+    let nonce = provider
+        .rand()
+        .random_vec(AEAD.nonce_size())
+        .map_err(|_| CryptoError::InsufficientRandomness)?;
+    let encrypted = provider
+        .crypto()
+        .aead_encrypt(AEAD, &key, data, &nonce, &[])?;
+    let hash = api.upload_blob(encrypted, client_uuids).await?;
+
+    Ok(Attachment {
+        hash: Base64(hash),
+        key: Base64(key),
+        nonce: Base64(nonce),
+    })
+}
+
+// the server can't swap the file, decrypting it would fail
+pub async fn download_attachment(
+    api: &BubbleApi,
+    provider: &impl OpenMlsCryptoProvider,
+    attachment: &Attachment,
+    client_uuid: Uuid,
+) -> Result<Vec<u8>, Error> {
+    let encrypted = api.download_blob(&attachment.hash, client_uuid).await?;
+    Ok(provider
+        .crypto()
+        .aead_decrypt(AEAD, &attachment.key, &encrypted, &attachment.nonce, &[])?)
+}
@@ -3,13 +3,19 @@ use crate::application_message::Message;
 use crate::helper::helper::parse_identity;
 
 use crate::mls_provider::MlsProvider;
+use crate::models::account::outbox::{Outbox, OutboxKind, MAX_ATTEMPTS};
+use crate::types::DbPool;
 use crate::Error;
 use openmls::framing::MlsMessageOut;
-use openmls::prelude::{GroupId, InnerState, LeafNodeIndex, Member, MlsGroup, TlsSerializeTrait};
+use openmls::prelude::{
+    GroupId, InnerState, LeafNodeIndex, Member, MlsGroup, MlsMessageIn, MlsMessageInBody,
+    ProcessedMessageContent, ProtocolMessage, TlsDeserializeTrait, TlsSerializeTrait,
+};
 use openmls_basic_credential::SignatureKeyPair;
 use openmls_traits::OpenMlsCryptoProvider;
 
 use log::warn;
+use reqwest::StatusCode;
 use std::ops::{Deref, DerefMut};
 use uuid::Uuid;
 
@@ -89,38 +95,368 @@ impl BubbleGroup {
         Ok(())
     }
 
-    pub async fn send_message(
+    async fn send_message(
         &self,
         api: &BubbleApi,
-        message: &MlsMessageOut,
+        message: Vec<u8>,
         exclude_client: &[Uuid],
+        members: Option<Vec<Uuid>>,
     ) -> Result<(), Error> {
-        let members = self.get_group_members()?;
-        let recipients = members
-            .into_iter()
-            .map(|m| m.client_uuid)
-            .filter(|uuid| !exclude_client.contains(uuid))
-            .collect::<Vec<_>>();
-        let bytes = message.tls_serialize_detached()?;
-        api.send_message(recipients, bytes, self.group_uuid).await?;
+        // the server fans the message out to the members before the commit, a winning commit changes them to `members`
+        let my_client_uuid = self.my_client_uuid()?;
+        let sent = api
+            .send_group_message(
+                my_client_uuid,
+                self.group_uuid,
+                message.clone(),
+                exclude_client.to_vec(),
+                members.clone(),
+            )
+            .await;
+        let sent = match sent {
+            // the group was created before the server knew about groups
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) && self.register(api).await? => {
+                api.send_group_message(
+                    my_client_uuid,
+                    self.group_uuid,
+                    message,
+                    exclude_client.to_vec(),
+                    members,
+                )
+                .await
+            }
+            sent => sent,
+        };
+        if let Some(winner) = sent? {
+            return Err(Error::CommitConflict(winner));
+        }
         Ok(())
     }
 
+    fn my_client_uuid(&self) -> Result<Uuid, Error> {
+        let own_index = self.group.own_leaf_index();
+        Ok(self
+            .get_group_members()?
+            .into_iter()
+            .find(|member| member.index == own_index)
+            .ok_or(Error::ReadClientUUID)?
+            .client_uuid)
+    }
+
+    // registers the group with the server with its current members, returns false if the server knows it already
+    async fn register(&self, api: &BubbleApi) -> Result<bool, Error> {
+        let my_client_uuid = self.my_client_uuid()?;
+        let members = self
+            .get_group_members()?
+            .into_iter()
+            .map(|member| member.client_uuid)
+            .filter(|client_uuid| *client_uuid != my_client_uuid)
+            .collect();
+        match api
+            .create_group(self.group_uuid, my_client_uuid, members)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(e) if e.status() == Some(StatusCode::CONFLICT) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // the clients of the group once our pending commit is merged
+    fn members_after_commit(&self) -> Result<Vec<Uuid>, Error> {
+        let staged = self.group.pending_commit().ok_or(Error::StaleCommit)?;
+        let removed: Vec<LeafNodeIndex> = staged
+            .remove_proposals()
+            .map(|queued| queued.remove_proposal().removed())
+            .collect();
+        let mut client_uuids: Vec<Uuid> = self
+            .get_group_members()?
+            .into_iter()
+            .filter(|member| !removed.contains(&member.index))
+            .map(|member| member.client_uuid)
+            .collect();
+        for queued in staged.add_proposals() {
+            let identity = queued
+                .add_proposal()
+                .key_package()
+                .leaf_node()
+                .credential()
+                .identity();
+            let (_, client_uuid) = parse_identity(identity).unwrap();
+            client_uuids.push(client_uuid);
+        }
+        Ok(client_uuids)
+    }
+
+    // queues an application message behind the group's other queued messages and tries to send them
+    // failing to send isn't an error, the message stays queued for the next flush
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_application_message(
         &mut self,
         mls_provider: &MlsProvider,
         api: &BubbleApi,
         signer: &SignatureKeyPair,
+        db: &DbPool,
         message: &Message,
         exclude_client: &[Uuid],
     ) -> Result<(), Error> {
-        warn!("send_application_message");
-        let mls_message = serde_json::to_string(message)?;
-        let mls_message_bytes = mls_message.as_bytes();
-        let mls_out = self
+        Outbox::new(
+            self.group_uuid,
+            OutboxKind::Application,
+            serde_json::to_vec(message)?,
+            exclude_client.to_vec(),
+        )
+        .create(db)
+        .await?;
+        if let Err(e) = self.flush_outbox(mls_provider, api, signer, db).await {
+            warn!("message for group {:?} queued: {}", self.group_uuid, e);
+        }
+        Ok(())
+    }
+
+    pub async fn send_proposal(
+        &mut self,
+        mls_provider: &MlsProvider,
+        api: &BubbleApi,
+        signer: &SignatureKeyPair,
+        db: &DbPool,
+        proposal: &MlsMessageOut,
+        exclude_client: &[Uuid],
+    ) -> Result<(), Error> {
+        Outbox::new(
+            self.group_uuid,
+            OutboxKind::Proposal,
+            proposal.tls_serialize_detached()?,
+            exclude_client.to_vec(),
+        )
+        .create(db)
+        .await?;
+        if let Err(e) = self.flush_outbox(mls_provider, api, signer, db).await {
+            warn!("proposal for group {:?} queued: {}", self.group_uuid, e);
+        }
+        Ok(())
+    }
+
+    // queues a key package of this client for the other members' clients and tries to send it
+    // returns whether it was delivered, otherwise it stays queued for the next flush or was dropped
+    pub async fn send_rejoin_request(
+        &mut self,
+        mls_provider: &MlsProvider,
+        api: &BubbleApi,
+        signer: &SignatureKeyPair,
+        db: &DbPool,
+        request: Vec<u8>,
+        client_uuids: Vec<Uuid>,
+    ) -> Result<bool, Error> {
+        let mut queued = Outbox::new(
+            self.group_uuid,
+            OutboxKind::RejoinRequest,
+            request,
+            client_uuids,
+        );
+        queued.create(db).await?;
+        if let Err(e) = self.flush_outbox(mls_provider, api, signer, db).await {
+            warn!(
+                "rejoin request for group {:?} queued: {}",
+                self.group_uuid, e
+            );
+        }
+        // sent entries are deleted, failed ones are kept
+        Ok(!Outbox::exists(db, queued.id).await?)
+    }
+
+    // queues our pending commit, followed by the welcome for the members it adds, and sends everything queued for the group
+    // returns whether the server accepted our commit, if another commit won the epoch ours was discarded and the winner merged instead
+    // on error the commit is still queued, a later flush sends and merges it
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_commit(
+        &mut self,
+        mls_provider: &MlsProvider,
+        api: &BubbleApi,
+        signer: &SignatureKeyPair,
+        db: &DbPool,
+        commit: &MlsMessageOut,
+        welcome: Option<(&MlsMessageOut, Vec<Uuid>)>,
+        exclude_client: &[Uuid],
+    ) -> Result<bool, Error> {
+        let mut queued = Outbox::new(
+            self.group_uuid,
+            OutboxKind::Commit,
+            commit.tls_serialize_detached()?,
+            exclude_client.to_vec(),
+        );
+        queued.create(db).await?;
+        if let Some((welcome, client_uuids)) = welcome {
+            Outbox::new(
+                self.group_uuid,
+                OutboxKind::Welcome,
+                welcome.tls_serialize_detached()?,
+                client_uuids,
+            )
+            .create(db)
+            .await?;
+        }
+        // the queued commit is only merged if the group still has it pending when it's sent
+        self.save_if_needed(mls_provider)?;
+        let rejected = self.flush_outbox(mls_provider, api, signer, db).await?;
+        Ok(!rejected.contains(&queued.id))
+    }
+
+    // sends the group's queued messages in order, stopping at the first one that fails, which is retried later
+    // returns the ids of our commits that lost their epoch to another commit
+    pub async fn flush_outbox(
+        &mut self,
+        mls_provider: &MlsProvider,
+        api: &BubbleApi,
+        signer: &SignatureKeyPair,
+        db: &DbPool,
+    ) -> Result<Vec<i32>, Error> {
+        let mut rejected = Vec::new();
+        // the welcomes of a commit that wasn't accepted would point new members at an epoch that doesn't exist
+        let mut drop_welcomes = false;
+        for mut queued in Outbox::from_group_uuid(db, self.group_uuid).await? {
+            if queued.kind == OutboxKind::Welcome && drop_welcomes {
+                Outbox::delete_by_id(db, queued.id).await?;
+                continue;
+            }
+            drop_welcomes = false;
+            if let Err(e) = self.send_queued(mls_provider, api, signer, &queued).await {
+                match e {
+                    Error::CommitConflict(winner) => {
+                        warn!("commit for group {:?} was rejected", self.group_uuid);
+                        self.group.clear_pending_commit();
+                        // we might have processed the winner already
+                        if protocol_message(&winner)?.epoch() == self.group.epoch() {
+                            self.merge_commit(mls_provider, &winner)?;
+                        }
+                        self.save_if_needed(mls_provider)?;
+                        rejected.push(queued.id);
+                        drop_welcomes = true;
+                    }
+                    Error::StaleCommit => {
+                        warn!("dropping stale commit for group {:?}", self.group_uuid);
+                        rejected.push(queued.id);
+                        drop_welcomes = true;
+                    }
+                    e if queued.attempts + 1 < MAX_ATTEMPTS && !permanent_failure(&e) => {
+                        queued.retry_later(db).await?;
+                        return Err(e);
+                    }
+                    e => {
+                        warn!(
+                            "dropping {} for group {:?}: {}",
+                            queued.kind.as_str(),
+                            self.group_uuid,
+                            e
+                        );
+                        queued.drop(db, e.to_string()).await?;
+                        if queued.kind == OutboxKind::Commit {
+                            self.group.clear_pending_commit();
+                            self.save_if_needed(mls_provider)?;
+                            rejected.push(queued.id);
+                            drop_welcomes = true;
+                        }
+                        continue;
+                    }
+                }
+            }
+            Outbox::delete_by_id(db, queued.id).await?;
+        }
+        Ok(rejected)
+    }
+
+    async fn send_queued(
+        &mut self,
+        mls_provider: &MlsProvider,
+        api: &BubbleApi,
+        signer: &SignatureKeyPair,
+        queued: &Outbox,
+    ) -> Result<(), Error> {
+        match queued.kind {
+            OutboxKind::Application => {
+                let mls_out = self
+                    .group
+                    .create_message(mls_provider, signer, &queued.message)?;
+                self.save_if_needed(mls_provider)?;
+                self.send_message(
+                    api,
+                    mls_out.tls_serialize_detached()?,
+                    &queued.client_uuids,
+                    None,
+                )
+                .await
+            }
+            OutboxKind::Commit => {
+                // our pending commit was replaced or a commit of another member was merged in the meantime
+                if self.group.pending_commit().is_none()
+                    || protocol_message(&queued.message)?.epoch() != self.group.epoch()
+                {
+                    return Err(Error::StaleCommit);
+                }
+                let members = self.members_after_commit()?;
+                self.send_message(
+                    api,
+                    queued.message.clone(),
+                    &queued.client_uuids,
+                    Some(members),
+                )
+                .await?;
+                self.group.merge_pending_commit(mls_provider)?;
+                self.save_if_needed(mls_provider)
+            }
+            OutboxKind::Proposal => {
+                self.send_message(api, queued.message.clone(), &queued.client_uuids, None)
+                    .await
+            }
+            OutboxKind::Welcome | OutboxKind::RejoinRequest => {
+                api.send_message(
+                    queued.client_uuids.clone(),
+                    queued.message.clone(),
+                    self.group_uuid,
+                )
+                .await?;
+                Ok(())
+            }
+        }
+    }
+
+    // processes and merges a serialized commit sent by another member
+    pub fn merge_commit(&mut self, mls_provider: &MlsProvider, commit: &[u8]) -> Result<(), Error> {
+        let message = protocol_message(commit)?;
+        match self
             .group
-            .create_message(mls_provider, signer, mls_message_bytes)?;
-        self.send_message(api, &mls_out, exclude_client).await?;
+            .process_message(mls_provider, message)?
+            .into_content()
+        {
+            ProcessedMessageContent::StagedCommitMessage(staged) => {
+                self.group.merge_staged_commit(mls_provider, *staged)?
+            }
+            _ => return Err(Error::ExpectedCommit),
+        }
         Ok(())
     }
 }
+
+// the server refused the request and sending it again won't change that, e.g. we were removed from the group
+fn permanent_failure(e: &Error) -> bool {
+    match e {
+        Error::Reqwest(e) => e.status().is_some_and(|status| {
+            status.is_client_error()
+                && ![
+                    StatusCode::UNAUTHORIZED,
+                    StatusCode::REQUEST_TIMEOUT,
+                    StatusCode::TOO_MANY_REQUESTS,
+                ]
+                .contains(&status)
+        }),
+        _ => false,
+    }
+}
+
+fn protocol_message(message: &[u8]) -> Result<ProtocolMessage, Error> {
+    match MlsMessageIn::tls_deserialize_exact(message)?.extract() {
+        MlsMessageInBody::PublicMessage(m) => Ok(m.into()),
+        MlsMessageInBody::PrivateMessage(m) => Ok(m.into()),
+        _ => Err(Error::ExpectedCommit),
+    }
+}
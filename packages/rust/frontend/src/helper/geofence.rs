@@ -0,0 +1,52 @@
+use crate::models::account::location::Location;
+use crate::models::account::place::Place;
+use crate::models::account::place_event::PlaceEvent;
+use crate::types::DbPool;
+
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+// metres between two points on the surface of the earth
+pub fn distance(latitude_a: f64, longitude_a: f64, latitude_b: f64, longitude_b: f64) -> f64 {
+    let latitude_a = latitude_a.to_radians();
+    let latitude_b = latitude_b.to_radians();
+    let delta_latitude = latitude_b - latitude_a;
+    let delta_longitude = (longitude_b - longitude_a).to_radians();
+
+    let a = (delta_latitude / 2.0).sin().powi(2)
+        + latitude_a.cos() * latitude_b.cos() * (delta_longitude / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+// records the places of the group the client entered or left with this location
+pub async fn record_place_events(db: &DbPool, location: &Location) -> Result<(), sqlx::Error> {
+    for place in Place::from_group_uuid(db, &location.group_uuid).await? {
+        let inside = distance(
+            place.latitude,
+            place.longitude,
+            location.latitude,
+            location.longitude,
+        ) <= place.radius;
+
+        let was_inside = match PlaceEvent::latest(db, &place.uuid, &location.client_uuid).await? {
+            // a location that arrived late can't tell us anything about the current state
+            Some(event) if event.event_date > location.location_date => continue,
+            Some(event) => event.entered,
+            None => false,
+        };
+        if inside == was_inside {
+            continue;
+        }
+
+        PlaceEvent {
+            id: 0,
+            place_uuid: place.uuid,
+            group_uuid: location.group_uuid,
+            client_uuid: location.client_uuid,
+            entered: inside,
+            event_date: location.location_date,
+        }
+        .create(db)
+        .await?;
+    }
+    Ok(())
+}
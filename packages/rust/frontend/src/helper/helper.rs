@@ -35,18 +35,22 @@ pub async fn get_this_client_mls_resources(
 }
 
 #[derive(Debug, thiserror::Error)]
-pub enum ParseIdentityError {}
+pub enum ParseIdentityError {
+    #[error("invalid identity")]
+    InvalidIdentity,
+}
 
 pub fn parse_identity(identity: &[u8]) -> Result<(Uuid, Uuid), ParseIdentityError> {
-    let identity = String::from_utf8(identity.to_vec()).expect("invalid identity");
+    let identity =
+        std::str::from_utf8(identity).map_err(|_| ParseIdentityError::InvalidIdentity)?;
     let parts: Vec<&str> = identity.split('_').collect();
 
-    if parts.len() != 3 {
-        panic!("invalid identity parts");
+    if parts.len() != 3 || parts[0] != "client" {
+        return Err(ParseIdentityError::InvalidIdentity);
     }
 
-    let user_uuid = Uuid::parse_str(parts[1]).expect("invalid user uuid");
-    let client_uuid = Uuid::parse_str(parts[2]).expect("invalid client uuid");
+    let user_uuid = Uuid::parse_str(parts[1]).map_err(|_| ParseIdentityError::InvalidIdentity)?;
+    let client_uuid = Uuid::parse_str(parts[2]).map_err(|_| ParseIdentityError::InvalidIdentity)?;
 
     Ok((user_uuid, client_uuid))
 }
@@ -0,0 +1,107 @@
+use crate::models::account::location::Location;
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+use uuid::Uuid;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+pub enum ExportFormat {
+    Gpx,
+    GeoJson,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "gpx" => Some(ExportFormat::Gpx),
+            "geojson" => Some(ExportFormat::GeoJson),
+            _ => None,
+        }
+    }
+
+    // `locations` have to be sorted by date
+    pub fn export(&self, client_uuid: &Uuid, locations: &[Location]) -> String {
+        match self {
+            ExportFormat::Gpx => to_gpx(client_uuid, locations),
+            ExportFormat::GeoJson => to_geojson(client_uuid, locations),
+        }
+    }
+}
+
+// a single track, gpx has no place for the other fields of a location
+fn to_gpx(client_uuid: &Uuid, locations: &[Location]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\" creator=\"Bubble\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    // writing to a String can't fail
+    writeln!(
+        out,
+        "  <trk>\n    <name>{}</name>\n    <trkseg>",
+        client_uuid
+    )
+    .unwrap();
+    for location in locations {
+        write!(
+            out,
+            "      <trkpt lat=\"{}\" lon=\"{}\">",
+            location.latitude, location.longitude
+        )
+        .unwrap();
+        if let Some(altitude) = location.altitude {
+            write!(out, "<ele>{}</ele>", altitude).unwrap();
+        }
+        writeln!(
+            out,
+            "<time>{}</time></trkpt>",
+            location.location_date.format(TIME_FORMAT)
+        )
+        .unwrap();
+    }
+    out.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    out
+}
+
+// a point feature per location, the fields we don't have are left out of its properties
+fn to_geojson(client_uuid: &Uuid, locations: &[Location]) -> String {
+    let features: Vec<_> = locations
+        .iter()
+        .map(|location| {
+            let mut properties = Map::new();
+            properties.insert("client_uuid".to_string(), json!(client_uuid));
+            properties.insert(
+                "time".to_string(),
+                json!(location.location_date.format(TIME_FORMAT).to_string()),
+            );
+            let optional = [
+                ("precision", location.precision.grid_metres().map(f64::from)),
+                ("altitude", location.altitude),
+                ("floor", location.floor.map(f64::from)),
+                ("course", location.course),
+                ("speed", location.speed),
+                ("horizontal_accuracy", location.horizontal_accuracy),
+                ("vertical_accuracy", location.vertical_accuracy),
+                ("course_accuracy", location.course_accuracy),
+                ("speed_accuracy", location.speed_accuracy),
+            ];
+            for (key, value) in optional {
+                if let Some(value) = value {
+                    properties.insert(key.to_string(), json!(value));
+                }
+            }
+            json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [location.longitude, location.latitude],
+                },
+                "properties": Value::Object(properties),
+            })
+        })
+        .collect();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+    .to_string()
+}
@@ -0,0 +1,89 @@
+use crate::application_message::{Location, LocationPrecision};
+use crate::Error;
+
+const METRES_PER_DEGREE: f64 = 111_320.0;
+// roughly the size of a city, receivers show the city rather than a point
+const CITY_METRES: u32 = 10_000;
+
+impl LocationPrecision {
+    // the size of the grid the location is snapped to, None if it's exact
+    pub fn grid_metres(&self) -> Option<u32> {
+        match self {
+            LocationPrecision::Exact => None,
+            LocationPrecision::Grid { metres } => Some(*metres),
+            LocationPrecision::City => Some(CITY_METRES),
+        }
+    }
+
+    // a grid without a size has no cells to snap to
+    pub fn check(&self) -> Result<(), Error> {
+        if self.grid_metres() == Some(0) {
+            return Err(Error::InvalidLocationPrecision);
+        }
+        Ok(())
+    }
+
+    // stored as the mode and the grid size, so rows from before the modes existed are grids
+    pub fn mode(&self) -> &'static str {
+        match self {
+            LocationPrecision::Exact => "exact",
+            LocationPrecision::Grid { .. } => "grid",
+            LocationPrecision::City => "city",
+        }
+    }
+
+    pub fn from_columns(mode: &str, metres: Option<u32>) -> Self {
+        match (mode, metres) {
+            ("city", _) => LocationPrecision::City,
+            (_, Some(metres)) => LocationPrecision::Grid { metres },
+            (_, None) => LocationPrecision::Exact,
+        }
+    }
+}
+
+// coarsens the location before it's sent, the readings besides the coordinates would give away more than the grid does
+pub fn apply_precision(location: &mut Location, precision: LocationPrecision) {
+    location.precision = precision;
+    let metres = match precision.grid_metres() {
+        Some(metres) if metres > 0 => metres,
+        _ => return,
+    };
+    (location.latitude, location.longitude) =
+        reduce_precision(location.latitude, location.longitude, metres);
+    location.altitude = None;
+    location.floor = None;
+    location.course = None;
+    location.speed = None;
+    location.vertical_accuracy = None;
+    location.course_accuracy = None;
+    location.speed_accuracy = None;
+    // receivers draw the accuracy circle, it has to cover the whole cell
+    location.horizontal_accuracy = Some(
+        location
+            .horizontal_accuracy
+            .unwrap_or_default()
+            .max(metres as f64),
+    );
+}
+
+// snaps a location to the centre of its cell in a grid of `precision` metres
+// we don't add random noise because the noise of repeated updates from the same place averages out
+fn reduce_precision(latitude: f64, longitude: f64, precision: u32) -> (f64, f64) {
+    if precision == 0 {
+        return (latitude, longitude);
+    }
+    let precision = precision as f64;
+
+    let latitude_step = precision / METRES_PER_DEGREE;
+    let latitude = (((latitude / latitude_step).floor() + 0.5) * latitude_step).clamp(-90.0, 90.0);
+
+    // a degree of longitude gets shorter towards the poles, so the cells are made as wide as they are high
+    let longitude_step = (precision / (METRES_PER_DEGREE * latitude.to_radians().cos())).min(360.0);
+    let mut longitude =
+        (((longitude + 180.0) / longitude_step).floor() + 0.5) * longitude_step - 180.0;
+    if longitude > 180.0 {
+        longitude -= 360.0;
+    }
+
+    (latitude, longitude)
+}
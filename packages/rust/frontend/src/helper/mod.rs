@@ -1,4 +1,9 @@
+pub mod attachment;
 pub mod bubble_group;
+pub mod geofence;
 pub mod helper;
+pub mod location_export;
+pub mod location_precision;
 mod mls_helper;
 pub mod resource_fetcher;
+pub mod timeline;
@@ -0,0 +1,131 @@
+use crate::helper::geofence::distance;
+use crate::models::account::location::Location;
+use crate::models::account::location_segment::LocationSegment;
+use crate::types::DbPool;
+use sqlx::types::chrono::NaiveDateTime;
+use uuid::Uuid;
+
+// metres a client can move around and still be staying at the same place
+const STAY_RADIUS: f64 = 100.0;
+// milliseconds a client has to stay within STAY_RADIUS for it to count as a stay
+const STAY_DURATION: i64 = 10 * 60 * 1000;
+
+fn trip(locations: &[Location]) -> LocationSegment {
+    let first = &locations[0];
+    let last = &locations[locations.len() - 1];
+    LocationSegment {
+        id: 0,
+        group_uuid: first.group_uuid,
+        client_uuid: first.client_uuid,
+        stay: false,
+        start_date: first.location_date,
+        end_date: last.location_date,
+        latitude: None,
+        longitude: None,
+        distance: locations
+            .windows(2)
+            .map(|pair| {
+                distance(
+                    pair[0].latitude,
+                    pair[0].longitude,
+                    pair[1].latitude,
+                    pair[1].longitude,
+                )
+            })
+            .sum(),
+    }
+}
+
+fn stay(locations: &[Location]) -> LocationSegment {
+    let first = &locations[0];
+    let last = &locations[locations.len() - 1];
+    let count = locations.len() as f64;
+    LocationSegment {
+        id: 0,
+        group_uuid: first.group_uuid,
+        client_uuid: first.client_uuid,
+        stay: true,
+        start_date: first.location_date,
+        end_date: last.location_date,
+        latitude: Some(locations.iter().map(|l| l.latitude).sum::<f64>() / count),
+        longitude: Some(locations.iter().map(|l| l.longitude).sum::<f64>() / count),
+        distance: 0.0,
+    }
+}
+
+// splits the locations of a client, sorted by date, into stays and the trips between them
+// a trip starts at the last location of the stay before it and ends at the first one of the stay after it
+pub fn segment(locations: &[Location]) -> Vec<LocationSegment> {
+    let mut segments = Vec::new();
+    let mut trip_start = None;
+    let mut i = 0;
+    while i < locations.len() {
+        let anchor = &locations[i];
+        let mut end = i;
+        while end + 1 < locations.len()
+            && distance(
+                anchor.latitude,
+                anchor.longitude,
+                locations[end + 1].latitude,
+                locations[end + 1].longitude,
+            ) <= STAY_RADIUS
+        {
+            end += 1;
+        }
+
+        let duration = locations[end].location_date.timestamp_millis()
+            - anchor.location_date.timestamp_millis();
+        if duration < STAY_DURATION {
+            trip_start.get_or_insert(i);
+            i += 1;
+            continue;
+        }
+
+        if let Some(start) = trip_start.take() {
+            segments.push(trip(&locations[start..=i]));
+        }
+        segments.push(stay(&locations[i..=end]));
+        trip_start = Some(end);
+        i = end + 1;
+    }
+
+    if let Some(start) = trip_start {
+        if start < locations.len() - 1 {
+            segments.push(trip(&locations[start..]));
+        }
+    }
+    segments
+}
+
+// brings the cached segments of the client up to date with its locations
+// locations that are stored invalidate the segments they could change, see store_location
+pub async fn update_segments(
+    db: &DbPool,
+    group_uuid: &Uuid,
+    client_uuid: &Uuid,
+) -> Result<(), sqlx::Error> {
+    // the last segment ends at the latest location that was segmented
+    if let Some(last) = LocationSegment::last(db, group_uuid, client_uuid, None).await? {
+        let after =
+            NaiveDateTime::from_timestamp_millis(last.end_date.timestamp_millis() + 1).unwrap();
+        let new =
+            Location::count_query(db, group_uuid, client_uuid, &after, &NaiveDateTime::MAX).await?;
+        if new == 0 {
+            return Ok(());
+        }
+    }
+
+    // the last stay might not be over yet, everything before it stays the same
+    let from = LocationSegment::last(db, group_uuid, client_uuid, Some(true))
+        .await?
+        .map(|stay| stay.start_date)
+        .unwrap_or(NaiveDateTime::MIN);
+    LocationSegment::delete_starting_from(db, group_uuid, client_uuid, &from).await?;
+
+    let locations =
+        Location::range(db, group_uuid, client_uuid, &from, &NaiveDateTime::MAX).await?;
+    for mut segment in segment(&locations) {
+        segment.create(db).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use crate::api::BubbleApi;
+use crate::helper::bubble_group::BubbleGroup;
+use crate::helper::helper::get_this_client_mls_resources;
+use crate::js_interface::client::new_key_package;
+use crate::js_interface::FrontendInstance;
+use crate::mls_provider::MlsProvider;
+use crate::models::account::group::Group as GroupModel;
+use crate::models::account::inbox::Inbox;
+use crate::models::account::outbox::Outbox;
+use crate::models::kv::AccountKv;
+use crate::types::DbPool;
+use crate::Error;
+use argon2::{Algorithm, Argon2, Params, Version};
+use bridge_macro::bridge;
+use common::base64;
+use common::base64::Base64;
+use ed25519_dalek::{PublicKey, SecretKey};
+use openmls::framing::MlsMessageOut;
+use openmls::prelude::TlsSerializeTrait;
+use openmls_rust_crypto::RustCrypto;
+use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::random::OpenMlsRand;
+use openmls_traits::types::{AeadType, CryptoError};
+use reqwest::StatusCode;
+use sqlx::sqlite::SqlitePoolOptions;
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+// the last byte is the format version, it decides the key derivation parameters
+const BACKUP_HEADER: &[u8] = b"bubble backup\x01";
+const SALT_SIZE: usize = 16;
+const AEAD: AeadType = AeadType::ChaCha20Poly1305;
+// argon2id with 19 MiB of memory and 2 passes
+const KDF_MEMORY: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+
+fn backup_key(passphrase: &str, salt: &[u8]) -> Vec<u8> {
+    let params = Params::new(KDF_MEMORY, KDF_ITERATIONS, 1, Some(AEAD.key_size())).unwrap();
+    let mut key = vec![0; AEAD.key_size()];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .unwrap();
+    key
+}
+
+// header || salt || nonce || ciphertext, the header, salt and nonce are authenticated too
+fn encrypt_backup(database: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let crypto = RustCrypto::default();
+    let salt = crypto
+        .random_vec(SALT_SIZE)
+        .map_err(|_| CryptoError::InsufficientRandomness)?;
+    let nonce = crypto
+        .random_vec(AEAD.nonce_size())
+        .map_err(|_| CryptoError::InsufficientRandomness)?;
+    let key = backup_key(passphrase, &salt);
+
+    let mut backup = [BACKUP_HEADER, &salt, &nonce].concat();
+    let ciphertext = crypto.aead_encrypt(AEAD, &key, database, &nonce, &backup)?;
+    backup.extend(ciphertext);
+    Ok(backup)
+}
+
+fn decrypt_backup(backup: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let prefix_size = BACKUP_HEADER.len() + SALT_SIZE + AEAD.nonce_size();
+    if backup.len() < prefix_size || !backup.starts_with(BACKUP_HEADER) {
+        return Err(Error::InvalidBackup);
+    }
+    let (prefix, ciphertext) = backup.split_at(prefix_size);
+    let (salt, nonce) = prefix[BACKUP_HEADER.len()..].split_at(SALT_SIZE);
+    let key = backup_key(passphrase, salt);
+
+    RustCrypto::default()
+        .aead_decrypt(AEAD, &key, ciphertext, nonce, prefix)
+        .map_err(|_| Error::InvalidBackup)
+}
+
+// the backed up client's group state is behind the groups', so the restored device gets a client of its own
+// the backed up client is revoked, the members that add the new client remove it from the groups
+// our groups are marked out of sync until the new client is added again, see request_rejoin
+async fn reset_client(account_db: &DbPool, api: &BubbleApi, user_uuid: &Uuid) -> Result<(), Error> {
+    sqlx::migrate!("./migrations/account")
+        .run(account_db)
+        .await?;
+
+    let user_private_key = base64::deserialize(
+        &AccountKv::get(account_db, "user_private_key")
+            .await?
+            .ok_or_else(|| Error::KeyStoreRead)?,
+    );
+    let user_private_key = SecretKey::from_bytes(&user_private_key)?;
+    // the backup has to belong to the account we logged in to
+    let public: PublicKey = (&user_private_key).into();
+    if api.get_user(user_uuid).await?.identity.0 != public.to_bytes() {
+        return Err(Error::IdentityMismatch);
+    }
+
+    if let Some(lost_client_uuid) = AccountKv::get(account_db, "client_uuid").await? {
+        let lost_client_uuid = Uuid::parse_str(&lost_client_uuid).unwrap();
+        match api.delete_client(&lost_client_uuid).await {
+            // the backup was restored before
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {}
+            deleted => deleted?,
+        }
+    }
+    AccountKv::delete(account_db, "client_uuid").await?;
+    AccountKv::delete(account_db, "client_public_signature_key").await?;
+    AccountKv::delete(account_db, "bearer").await?;
+    Inbox::delete_all(account_db).await?;
+    Outbox::delete_all(account_db).await?;
+    for mut group in GroupModel::all_in_group(account_db).await? {
+        group.out_of_sync = true;
+        group.update(account_db).await?;
+    }
+    Ok(())
+}
+
+impl FrontendInstance {
+    // the account database encrypted with a key derived from the passphrase
+    #[bridge]
+    pub async fn export_backup(&self, passphrase: String) -> Result<Base64, Error> {
+        if passphrase.is_empty() {
+            return Err(Error::EmptyPassphrase);
+        }
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let path = format!(
+            "{}/accounts/{}.backup.db",
+            &self.static_data.data_directory, global_data.user_uuid
+        );
+        if Path::new(&path).exists() {
+            fs::remove_file(&path)?;
+        }
+
+        // a consistent copy while the database stays in use
+        sqlx::query("VACUUM INTO $1")
+            .bind(&path)
+            .execute(&global_data.database)
+            .await?;
+        let database = fs::read(&path)?;
+        fs::remove_file(&path)?;
+
+        Ok(Base64(encrypt_backup(&database, &passphrase)?))
+    }
+
+    // restores the backup on this device, logs in and asks the other members of our groups to add the restored client
+    #[bridge]
+    pub async fn restore_backup(
+        &self,
+        backup: Base64,
+        passphrase: String,
+        username_or_email: String,
+        password: String,
+    ) -> Result<Uuid, Error> {
+        if self.logged_in().await {
+            return Err(Error::LoggedIn);
+        }
+        let database = decrypt_backup(&backup.0, &passphrase)?;
+        let api = BubbleApi::new(self.static_data.domain.clone(), None);
+        let session = api.login(username_or_email, password).await?;
+        let user_uuid = session.user_uuid;
+        let api = BubbleApi::new(
+            self.static_data.domain.clone(),
+            Some(session.bearer.to_string()),
+        );
+
+        let accounts = format!("{}/accounts", &self.static_data.data_directory);
+        fs::create_dir_all(&accounts)?;
+        let restore_path = format!("{}/{}.restore.db", accounts, user_uuid);
+        fs::write(&restore_path, database)?;
+        let account_db = SqlitePoolOptions::new()
+            .connect(&format!("sqlite:{}", restore_path))
+            .await?;
+        let reset = reset_client(&account_db, &api, &user_uuid).await;
+        account_db.close().await;
+        if let Err(e) = reset {
+            fs::remove_file(&restore_path)?;
+            return Err(e);
+        }
+        fs::rename(&restore_path, format!("{}/{}.db", accounts, user_uuid))?;
+
+        self.login_with_token(session).await?;
+        self.replace_key_packages().await?;
+        self.request_rejoin().await?;
+        Ok(user_uuid)
+    }
+
+    // sends a key package of this client to the other users' clients in our out of sync groups, they add this client
+    // if it belongs to a member of the group, our own devices add it when they sync clients
+    // returns how many groups were asked, the requests that couldn't be sent yet are retried with the outbox
+    #[bridge]
+    pub async fn request_rejoin(&self) -> Result<usize, Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let user_uuid = &global_data.user_uuid;
+        let client_uuid = global_data
+            .client_uuid
+            .read()
+            .await
+            .ok_or_else(|| Error::ReadClientUUID)?;
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) =
+            get_this_client_mls_resources(user_uuid, &client_uuid, account_db, &mls_provider)
+                .await?;
+
+        // the key package is signed with this client's key, so only this client can ask for it to be added
+        let key_package = new_key_package(user_uuid, &client_uuid, &mls_provider, &signature)?;
+        let request = MlsMessageOut::from(key_package).tls_serialize_detached()?;
+
+        let mut asked = 0;
+        for group_model in GroupModel::all_in_group(account_db).await? {
+            if !group_model.out_of_sync {
+                continue;
+            }
+            let mut group = BubbleGroup::new_from_uuid(&group_model.uuid, &mls_provider)
+                .ok_or_else(|| Error::MLSGroupLoad)?;
+            // the group state is the backup's, our clients in it might not exist anymore
+            let clients: Vec<_> = group
+                .get_group_members()?
+                .into_iter()
+                .filter(|member| member.user_uuid != *user_uuid)
+                .map(|member| member.client_uuid)
+                .collect();
+            if clients.is_empty() {
+                continue;
+            }
+            // a request that can't be sent now is retried when the outbox is flushed
+            if group
+                .send_rejoin_request(
+                    &mls_provider,
+                    &api,
+                    &signature,
+                    account_db,
+                    request.clone(),
+                    clients,
+                )
+                .await?
+            {
+                asked += 1;
+            }
+            group.save_if_needed(&mls_provider)?;
+        }
+        Ok(asked)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::api::BubbleApi;
+use crate::application_message::{Message, Text};
+use crate::helper::bubble_group::BubbleGroup;
+use crate::helper::helper::get_this_client_mls_resources;
+use crate::js_interface::FrontendInstance;
+use crate::mls_provider::MlsProvider;
+use crate::models::account::chat_message::ChatMessage as ChatMessageModel;
+use crate::Error;
+use bridge_macro::bridge;
+use openmls::group::MlsGroup;
+use openmls::prelude::GroupId;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use uuid::Uuid;
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChatMessage {
+    pub uuid: Uuid,
+    pub client_uuid: Uuid,
+    pub body: String,
+    pub reply_to: Option<Uuid>,
+    pub timestamp: i64,
+}
+
+impl From<ChatMessageModel> for ChatMessage {
+    fn from(message: ChatMessageModel) -> Self {
+        Self {
+            uuid: message.uuid,
+            client_uuid: message.client_uuid,
+            body: message.body,
+            reply_to: message.reply_to,
+            timestamp: message.message_date.timestamp_millis(),
+        }
+    }
+}
+
+impl FrontendInstance {
+    // sends a text message to the group and stores it so it shows up in get_messages, returns its uuid
+    #[bridge]
+    pub async fn send_text(
+        &self,
+        group_uuid: Uuid,
+        body: String,
+        reply_to: Option<Uuid>,
+    ) -> Result<Uuid, Error> {
+        if body.trim().is_empty() {
+            return Err(Error::EmptyMessage);
+        }
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let client_uuid = global_data.client_uuid.read().await.unwrap();
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await?;
+        let mut group = BubbleGroup::new(
+            MlsGroup::load(&GroupId::from_slice(group_uuid.as_ref()), &mls_provider)
+                .ok_or_else(|| Error::MLSGroupLoad)?,
+        );
+
+        let text = Text {
+            id: Uuid::new_v4(),
+            body,
+            reply_to,
+            timestamp: Utc::now().timestamp_millis(),
+        };
+        let mut message = ChatMessageModel::new(client_uuid, group_uuid, &text);
+        let text = Message::Text(text);
+
+        group
+            .send_application_message(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &text,
+                &[client_uuid],
+            )
+            .await?;
+        group.save_if_needed(&mls_provider)?;
+        message.create(account_db).await?;
+
+        Ok(message.uuid)
+    }
+
+    // the messages of the group sent before the timestamp, newest first
+    #[bridge]
+    pub async fn get_messages(
+        &self,
+        group_uuid: Uuid,
+        before_timestamp: i64,
+        amount: u32,
+    ) -> Result<Vec<ChatMessage>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let before = NaiveDateTime::from_timestamp_millis(before_timestamp).unwrap();
+        let messages =
+            ChatMessageModel::query(&global_data.database, &group_uuid, &before, amount).await?;
+
+        Ok(messages.into_iter().map(ChatMessage::from).collect())
+    }
+}
@@ -1,14 +1,48 @@
 use crate::api::BubbleApi;
 use crate::helper::helper::get_this_client_mls_resources;
+use crate::helper::resource_fetcher::ResourceFetcher;
+use crate::js_interface::group::sync_group_clients;
 use crate::js_interface::FrontendInstance;
 use crate::mls_provider::MlsProvider;
 use crate::types::CIPHERSUITE;
 use crate::Error;
 use bridge_macro::bridge;
+use log::warn;
 use openmls::prelude::{
     Credential, CredentialType, CredentialWithKey, CryptoConfig, KeyPackage, ProtocolVersion,
     SignaturePublicKey,
 };
+use openmls_basic_credential::SignatureKeyPair;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// receiving messages syncs our clients at most this often
+const CLIENT_SYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub(crate) fn new_key_package(
+    user_uuid: &Uuid,
+    client_uuid: &Uuid,
+    mls_provider: &MlsProvider,
+    signature: &SignatureKeyPair,
+) -> Result<KeyPackage, Error> {
+    let identity = format!("client_{}_{}", user_uuid, client_uuid);
+    let credential = Credential::new(identity.into_bytes(), CredentialType::Basic)?;
+    let key_package = KeyPackage::builder()
+        .build(
+            CryptoConfig {
+                ciphersuite: CIPHERSUITE,
+                version: ProtocolVersion::default(),
+            },
+            mls_provider,
+            signature,
+            CredentialWithKey {
+                credential,
+                signature_key: SignaturePublicKey::from(signature.public()),
+            },
+        )
+        .unwrap();
+    Ok(key_package)
+}
 
 impl FrontendInstance {
     #[bridge]
@@ -27,34 +61,117 @@ impl FrontendInstance {
             get_this_client_mls_resources(user_uuid, &client_uuid, account_db, &mls_provider)
                 .await?;
 
-        let identity = format!("client_{}_{}", user_uuid, client_uuid);
-        let credential = Credential::new(identity.into_bytes(), CredentialType::Basic)?;
-        let public = SignaturePublicKey::from(signature.public());
-
         let num_key_packages = 100;
 
         let mut key_packages = Vec::with_capacity(num_key_packages);
 
         for _ in 0..num_key_packages {
-            let key_package = KeyPackage::builder()
-                .build(
-                    CryptoConfig {
-                        ciphersuite: CIPHERSUITE,
-                        version: ProtocolVersion::default(),
-                    },
-                    &mls_provider,
-                    &signature,
-                    CredentialWithKey {
-                        credential: credential.clone(),
-                        signature_key: public.clone(),
-                    },
-                )
-                .unwrap();
-            key_packages.push(key_package);
+            key_packages.push(new_key_package(
+                user_uuid,
+                &client_uuid,
+                &mls_provider,
+                &signature,
+            )?);
         }
 
         api.replace_key_packages(&client_uuid, key_packages).await?;
 
         Ok(())
     }
+
+    // the token the platform's push service gave this device, the server uses it to wake us up when messages arrive
+    #[bridge]
+    pub async fn register_push_token(&self, token: String) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let client_uuid = global_data
+            .client_uuid
+            .read()
+            .await
+            .ok_or_else(|| Error::ReadClientUUID)?;
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+
+        api.register_push_token(&client_uuid, token).await?;
+
+        Ok(())
+    }
+
+    #[bridge]
+    pub async fn revoke_push_token(&self) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let client_uuid = global_data
+            .client_uuid
+            .read()
+            .await
+            .ok_or_else(|| Error::ReadClientUUID)?;
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+
+        api.revoke_push_token(&client_uuid).await?;
+
+        Ok(())
+    }
+
+    // adds our other clients, e.g. a newly linked device, to every group they're missing from
+    // and removes the ones that no longer exist, returns how many groups were changed
+    #[bridge]
+    pub async fn sync_clients(&self) -> Result<usize, Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let user_uuid = &global_data.user_uuid;
+        let client_uuid = global_data
+            .client_uuid
+            .read()
+            .await
+            .ok_or_else(|| Error::ReadClientUUID)?;
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) =
+            get_this_client_mls_resources(user_uuid, &client_uuid, account_db, &mls_provider)
+                .await?;
+
+        // only clients signed by our identity key are added
+        let resource_fetcher = ResourceFetcher::new(api.clone(), account_db.clone());
+        let clients: Vec<_> = resource_fetcher
+            .get_clients_full_authentication(user_uuid)
+            .await?
+            .into_iter()
+            .map(|client| client.uuid)
+            .collect();
+
+        sync_group_clients(
+            &mls_provider,
+            &api,
+            &signature,
+            account_db,
+            user_uuid,
+            &clients,
+            client_uuid,
+        )
+        .await
+    }
+
+    pub(crate) async fn sync_clients_if_due(&self) {
+        {
+            let mut last_sync = self.last_client_sync.lock().unwrap();
+            if matches!(*last_sync, Some(last_sync) if last_sync.elapsed() < CLIENT_SYNC_INTERVAL) {
+                return;
+            }
+            *last_sync = Some(Instant::now());
+        }
+        if let Err(e) = self.sync_clients().await {
+            warn!("unable to sync clients: {}", e);
+        }
+    }
 }
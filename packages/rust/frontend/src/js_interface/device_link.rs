@@ -0,0 +1,193 @@
+use crate::api::BubbleApi;
+use crate::helper::helper::get_this_client_mls_resources;
+use crate::helper::resource_fetcher::ResourceFetcher;
+use crate::js_interface::group::sync_group_clients;
+use crate::js_interface::FrontendInstance;
+use crate::mls_provider::MlsProvider;
+use crate::models::kv::AccountKv;
+use crate::Error;
+use bridge_macro::bridge;
+use common::base64;
+use common::device_link;
+use common::http_types::SessionTokenResponse;
+use ed25519_dalek::{PublicKey, SecretKey};
+use openmls_rust_crypto::RustCrypto;
+use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::random::OpenMlsRand;
+use openmls_traits::types::{
+    CryptoError, HpkeAeadType, HpkeCiphertext, HpkeConfig, HpkeKdfType, HpkeKemType,
+};
+use openmls_traits::OpenMlsCryptoProvider;
+use tls_codec::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const HPKE_CONFIG: HpkeConfig = HpkeConfig(
+    HpkeKemType::DhKem25519,
+    HpkeKdfType::HkdfSha256,
+    HpkeAeadType::ChaCha20Poly1305,
+);
+const HPKE_INFO: &[u8] = b"bubble device link";
+
+// a device that logged in to an account it doesn't have the identity key of
+pub struct PendingDeviceLink {
+    code: String,
+    session: SessionTokenResponse,
+    // the identity key is encrypted to this key's public half
+    private_key: Vec<u8>,
+}
+
+impl FrontendInstance {
+    // logs in on a new device and returns the code to enter on a device that is already logged in
+    #[bridge]
+    pub async fn start_device_link(
+        &self,
+        username_or_email: String,
+        password: String,
+    ) -> Result<String, Error> {
+        let api = BubbleApi::new(self.static_data.domain.clone(), None);
+        let session = api.login(username_or_email, password).await?;
+        let crypto = RustCrypto::default();
+        let ikm = crypto
+            .random_vec(32)
+            .map_err(|_| CryptoError::InsufficientRandomness)?;
+        let key_pair = crypto.derive_hpke_keypair(HPKE_CONFIG, &ikm);
+
+        let api = BubbleApi::new(
+            self.static_data.domain.clone(),
+            Some(session.bearer.to_string()),
+        );
+        let code = api.create_device_link(key_pair.public).await?;
+
+        *self.device_link.lock().await = Some(PendingDeviceLink {
+            code: code.clone(),
+            session,
+            private_key: key_pair.private.to_vec(),
+        });
+        Ok(code)
+    }
+
+    // logs in once the other device sent the identity key, null until then
+    // our groups are joined when their welcomes are received
+    #[bridge]
+    pub async fn finish_device_link(&self) -> Result<Option<Uuid>, Error> {
+        let mut pending = self.device_link.lock().await;
+        let link = pending.as_ref().ok_or_else(|| Error::NoDeviceLink)?;
+        let user_uuid = link.session.user_uuid;
+        let api = BubbleApi::new(
+            self.static_data.domain.clone(),
+            Some(link.session.bearer.to_string()),
+        );
+        let Some(encrypted_key) = api.get_device_link(&link.code).await?.encrypted_key else {
+            return Ok(None);
+        };
+
+        let ciphertext = HpkeCiphertext::tls_deserialize_exact(&encrypted_key.0)?;
+        let user_private_key = RustCrypto::default().hpke_open(
+            HPKE_CONFIG,
+            &ciphertext,
+            &link.private_key,
+            HPKE_INFO,
+            &[],
+        )?;
+        let user_private_key = SecretKey::from_bytes(&user_private_key)?;
+        // the server could have encrypted a key of its own to us
+        let public: PublicKey = (&user_private_key).into();
+        if api.get_user(&user_uuid).await?.identity.0 != public.to_bytes() {
+            return Err(Error::IdentityMismatch);
+        }
+
+        self.create_account_database(&user_uuid, &user_private_key)
+            .await?;
+        // the link stays pending until we are logged in, so a failed login can be retried
+        self.login_with_token(link.session.clone()).await?;
+        let link = pending.take().unwrap();
+        self.replace_key_packages().await?;
+
+        // the other device adds the client to our groups once it has key packages
+        let client_uuid = self
+            .account_data
+            .read()
+            .await
+            .as_ref()
+            .ok_or_else(|| Error::NoGlobalAccountData)?
+            .client_uuid
+            .read()
+            .await
+            .ok_or_else(|| Error::ReadClientUUID)?;
+        api.update_device_link(&link.code, None, Some(client_uuid))
+            .await?;
+        Ok(Some(user_uuid))
+    }
+
+    // sends our identity key to the device showing the code and adds the client it creates to our groups
+    // returns false until that client exists, call it again then
+    #[bridge]
+    pub async fn link_device(&self, code: String) -> Result<bool, Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let user_uuid = global_data.user_uuid;
+        let my_client_uuid = global_data.client_uuid.read().await.unwrap();
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let link = api.get_device_link(&code).await?;
+        // the server could have swapped the new device's key for one of its own
+        if device_link::code(&link.public_key) != code {
+            return Err(Error::DeviceLinkKeyMismatch);
+        }
+
+        if link.encrypted_key.is_none() {
+            let user_private_key = base64::deserialize(
+                &AccountKv::get(account_db, "user_private_key")
+                    .await?
+                    .ok_or_else(|| Error::KeyStoreRead)?,
+            );
+            let ciphertext = mls_provider.crypto().hpke_seal(
+                HPKE_CONFIG,
+                &link.public_key,
+                HPKE_INFO,
+                &[],
+                &user_private_key,
+            );
+            api.update_device_link(&code, Some(ciphertext.tls_serialize_detached()?), None)
+                .await?;
+        }
+        let Some(new_client_uuid) = link.client_uuid else {
+            return Ok(false);
+        };
+
+        // the client has to be signed by our identity key
+        let resource_fetcher = ResourceFetcher::new(api.clone(), account_db.clone());
+        let clients: Vec<_> = resource_fetcher
+            .get_clients_full_authentication(&user_uuid)
+            .await?
+            .into_iter()
+            .map(|client| client.uuid)
+            .collect();
+        if !clients.contains(&new_client_uuid) {
+            return Err(Error::NoClientsFound);
+        }
+        let (signature, _) =
+            get_this_client_mls_resources(&user_uuid, &my_client_uuid, account_db, &mls_provider)
+                .await?;
+
+        // groups that fail are retried by the next client sync
+        sync_group_clients(
+            &mls_provider,
+            &api,
+            &signature,
+            account_db,
+            &user_uuid,
+            &clients,
+            my_client_uuid,
+        )
+        .await?;
+
+        api.delete_device_link(&code).await?;
+        Ok(true)
+    }
+}
@@ -1,24 +1,103 @@
 use crate::api::BubbleApi;
-use crate::application_message::{GroupStatus, Message};
-use crate::helper::bubble_group::BubbleGroup;
+use crate::application_message::LocationPrecision;
+use crate::application_message::{Attachment, GroupStatus, Message};
+use crate::helper::attachment::{download_attachment, upload_attachment};
+use crate::helper::bubble_group::{BubbleGroup, BubbleMember};
 use crate::helper::helper::get_this_client_mls_resources;
-use crate::helper::resource_fetcher::ResourceFetcher;
+use crate::helper::resource_fetcher::{ResourceError, ResourceFetcher};
 use crate::js_interface::user::UserOut;
 use crate::js_interface::FrontendInstance;
 use crate::mls_provider::MlsProvider;
 use crate::models::account::group::Group as GroupModel;
-use crate::types::MLS_GROUP_CONFIG;
+use crate::types::{DbPool, MLS_GROUP_CONFIG};
 use crate::Error;
 use bridge_macro::bridge;
 use common::base64::Base64;
+use log::warn;
 use openmls::group::MlsGroup;
-use openmls::prelude::{GroupId, ProtocolVersion, TlsSerializeTrait};
+use openmls::prelude::{GroupId, ProtocolVersion};
+use openmls_basic_credential::SignatureKeyPair;
 use openmls_traits::OpenMlsCryptoProvider;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use sqlx::types::chrono::{NaiveDateTime, Utc};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+// a commit is rebuilt after every commit of another member that won the epoch
+const MAX_COMMIT_ATTEMPTS: usize = 3;
+// bytes, larger images are rejected
+const MAX_IMAGE_SIZE: usize = 1024 * 1024;
+// bytes, larger images are sent as an attachment instead of inside the group status
+const INLINE_IMAGE_SIZE: usize = 16 * 1024;
+
+fn check_image(image: &[u8]) -> Result<(), Error> {
+    if image.len() > MAX_IMAGE_SIZE {
+        return Err(Error::ImageTooLarge(MAX_IMAGE_SIZE));
+    }
+    let png = image.starts_with(b"\x89PNG\r\n\x1a\n");
+    let jpeg = image.starts_with(&[0xff, 0xd8, 0xff]);
+    let webp = image.len() >= 12 && image.starts_with(b"RIFF") && &image[8..12] == b"WEBP";
+    if !png && !jpeg && !webp {
+        return Err(Error::InvalidImageFormat);
+    }
+    Ok(())
+}
+
+// returns the id of the status with it, the members acknowledge the status with the id
+// the attachment is uploaded for the other members of the group, it's kept on the group model and sent again
+// until the image or the members change, the caller saves the model
+async fn group_status(
+    api: &BubbleApi,
+    mls_provider: &MlsProvider,
+    group: &BubbleGroup,
+    client_uuid: &Uuid,
+    group_model: &mut GroupModel,
+    name: Option<String>,
+    image: Option<Vec<u8>>,
+) -> Result<(Uuid, Message), Error> {
+    let (image, image_attachment) = match image {
+        Some(image) if image.len() > INLINE_IMAGE_SIZE => {
+            let mut recipients: Vec<Uuid> = group
+                .get_group_members()?
+                .into_iter()
+                .map(|member| member.client_uuid)
+                .filter(|member| member != client_uuid)
+                .collect();
+            recipients.sort_unstable();
+            let uploaded = group_model.image.as_ref() == Some(&image)
+                && group_model.image_attachment_clients.as_ref() == Some(&recipients);
+            let attachment = match &group_model.image_attachment {
+                Some(attachment) if uploaded => attachment.clone(),
+                _ => {
+                    let attachment =
+                        upload_attachment(api, mls_provider, &image, recipients.clone()).await?;
+                    group_model.image_attachment = Some(attachment.clone());
+                    group_model.image_attachment_clients = Some(recipients);
+                    attachment
+                }
+            };
+            (None, Some(attachment))
+        }
+        image => {
+            group_model.image_attachment = None;
+            group_model.image_attachment_clients = None;
+            (image.map(Base64), None)
+        }
+    };
+    let id = Uuid::new_v4();
+    Ok((
+        id,
+        Message::GroupStatus(GroupStatus {
+            id: Some(id),
+            name,
+            image,
+            image_attachment,
+        }),
+    ))
+}
+
 #[bridge]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UserGroupInfo {
@@ -33,6 +112,204 @@ pub struct Group {
     pub name: Option<String>,
     pub image: Option<Vec<u8>>,
     pub members: HashMap<Uuid, UserGroupInfo>,
+    pub out_of_sync: bool,
+}
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LocationSettings {
+    pub enabled: bool,
+    // milliseconds since the epoch, sharing resumes afterwards
+    pub paused_until: Option<i64>,
+    pub precision: LocationPrecision,
+}
+
+// adds the clients to the group, once the server accepted our commit they are sent a welcome
+// the clients have to be authenticated already
+pub(crate) async fn add_clients(
+    mls_provider: &MlsProvider,
+    api: &BubbleApi,
+    signature: &SignatureKeyPair,
+    account_db: &DbPool,
+    group: &mut BubbleGroup,
+    client_uuids: Vec<Uuid>,
+    my_client_uuid: Uuid,
+) -> Result<(), Error> {
+    let mut client_uuids = client_uuids;
+    let mut key_packages = Vec::with_capacity(client_uuids.len());
+    for client_uuid in &client_uuids {
+        let key_package_in = api.request_key_package(client_uuid).await?;
+        let key_package = key_package_in
+            .validate(mls_provider.crypto(), ProtocolVersion::default())
+            .unwrap();
+        key_packages.push(key_package);
+    }
+
+    let mut attempts = 0;
+    loop {
+        let (mls_message_out, welcome_out, _group_info) =
+            group.add_members(mls_provider, signature, &key_packages)?;
+
+        // the server still has the old members, we don't want to send the message to ourselves
+        // the welcome is only sent once the server accepted the commit, otherwise the new members would join an epoch that doesn't exist
+        if group
+            .send_commit(
+                mls_provider,
+                api,
+                signature,
+                account_db,
+                &mls_message_out,
+                Some((&welcome_out, client_uuids.clone())),
+                &[my_client_uuid],
+            )
+            .await?
+        {
+            break;
+        }
+        attempts += 1;
+        if attempts == MAX_COMMIT_ATTEMPTS {
+            return Err(Error::CommitRejected);
+        }
+
+        // the commit that won might have added some of the clients already, e.g. another device of ours syncing them
+        let members: HashSet<_> = group
+            .get_group_members()?
+            .into_iter()
+            .map(|member| member.client_uuid)
+            .collect();
+        let (remaining, remaining_key_packages): (Vec<_>, Vec<_>) = client_uuids
+            .into_iter()
+            .zip(key_packages)
+            .filter(|(client_uuid, _)| !members.contains(client_uuid))
+            .unzip();
+        if remaining.is_empty() {
+            break;
+        }
+        client_uuids = remaining;
+        key_packages = remaining_key_packages;
+    }
+    Ok(())
+}
+
+// removes the members the filter matches, the removed clients aren't sent the commit
+pub(crate) async fn remove_clients(
+    mls_provider: &MlsProvider,
+    api: &BubbleApi,
+    signature: &SignatureKeyPair,
+    account_db: &DbPool,
+    group: &mut BubbleGroup,
+    filter: impl Fn(&BubbleMember) -> bool,
+    my_client_uuid: Uuid,
+) -> Result<(), Error> {
+    let mut attempts = 0;
+    loop {
+        // the winning commit may have changed the members, so they are looked up on every attempt
+        let (members_to_remove, mut exclude): (Vec<_>, Vec<_>) = group
+            .get_group_members()?
+            .into_iter()
+            .filter(&filter)
+            .map(|m| (m.index, m.client_uuid))
+            .unzip();
+        if members_to_remove.is_empty() {
+            break;
+        }
+        exclude.push(my_client_uuid);
+
+        let (mls_message_out, welcome_out, _group_info) =
+            group.remove_members(mls_provider, signature, &members_to_remove)?;
+
+        if welcome_out.is_some() {
+            return Err(Error::UnexpectedWelcome);
+        }
+
+        if group
+            .send_commit(
+                mls_provider,
+                api,
+                signature,
+                account_db,
+                &mls_message_out,
+                None,
+                &exclude,
+            )
+            .await?
+        {
+            break;
+        }
+        attempts += 1;
+        if attempts == MAX_COMMIT_ATTEMPTS {
+            return Err(Error::CommitRejected);
+        }
+    }
+    Ok(())
+}
+
+// adds our clients, e.g. a newly linked device, to every group we're in that they're missing from
+// and removes our clients that no longer exist, e.g. the client of a device that was restored from a backup
+// returns how many groups were changed, a group that fails is skipped and retried by the next sync
+pub(crate) async fn sync_group_clients(
+    mls_provider: &MlsProvider,
+    api: &BubbleApi,
+    signature: &SignatureKeyPair,
+    account_db: &DbPool,
+    user_uuid: &Uuid,
+    client_uuids: &[Uuid],
+    my_client_uuid: Uuid,
+) -> Result<usize, Error> {
+    let stale = |member: &BubbleMember| {
+        member.user_uuid == *user_uuid && !client_uuids.contains(&member.client_uuid)
+    };
+    let mut changed = 0;
+    for group_model in GroupModel::all_in_group(account_db).await? {
+        // our state is behind the group's, we can't commit until we're added again
+        if group_model.out_of_sync {
+            continue;
+        }
+        let mut group = BubbleGroup::new_from_uuid(&group_model.uuid, mls_provider)
+            .ok_or_else(|| Error::MLSGroupLoad)?;
+        let members = group.get_group_members()?;
+        let member_clients: HashSet<_> = members.iter().map(|member| member.client_uuid).collect();
+        let missing: Vec<_> = client_uuids
+            .iter()
+            .filter(|client_uuid| !member_clients.contains(client_uuid))
+            .copied()
+            .collect();
+        if missing.is_empty() && !members.iter().any(stale) {
+            continue;
+        }
+
+        let mut synced = remove_clients(
+            mls_provider,
+            api,
+            signature,
+            account_db,
+            &mut group,
+            stale,
+            my_client_uuid,
+        )
+        .await;
+        if synced.is_ok() && !missing.is_empty() {
+            synced = add_clients(
+                mls_provider,
+                api,
+                signature,
+                account_db,
+                &mut group,
+                missing,
+                my_client_uuid,
+            )
+            .await;
+        }
+        group.save_if_needed(mls_provider)?;
+        match synced {
+            Ok(()) => changed += 1,
+            Err(e) => warn!(
+                "unable to sync the clients of group {}: {}",
+                group_model.uuid, e
+            ),
+        }
+    }
+    Ok(changed)
 }
 
 impl FrontendInstance {
@@ -58,17 +335,27 @@ impl FrontendInstance {
             let members = mls_group.get_group_members()?;
             let mut out_members: HashMap<_, _> = HashMap::with_capacity(members.len());
             for member in members {
-                let client = resource_fetcher
+                let user_uuid = match resource_fetcher
                     .get_client_partial_authentication(&member.client_uuid)
-                    .await?;
+                    .await
+                {
+                    Ok(client) => client.user_uuid,
+                    // the client was deleted, e.g. a lost device's, and no sync has removed it yet
+                    Err(ResourceError::ReqwestError(e))
+                        if e.status() == Some(StatusCode::NOT_FOUND) =>
+                    {
+                        member.user_uuid
+                    }
+                    Err(e) => return Err(e.into()),
+                };
                 let user = resource_fetcher
-                    .get_user_partial_authentication(&client.user_uuid)
+                    .get_user_partial_authentication(&user_uuid)
                     .await?;
                 out_members
-                    .entry(client.user_uuid)
+                    .entry(user_uuid)
                     .or_insert_with(|| UserGroupInfo {
                         info: UserOut {
-                            uuid: client.user_uuid,
+                            uuid: user_uuid,
                             username: user.username,
                             name: user.name,
                             primary_client_uuid: user.primary_client_uuid,
@@ -84,6 +371,7 @@ impl FrontendInstance {
                 name: group.name,
                 image: group.image,
                 members: out_members,
+                out_of_sync: group.out_of_sync,
             });
         }
         Ok(out)
@@ -96,19 +384,25 @@ impl FrontendInstance {
         let account_db = &account_data.database;
         let user_uuid = &account_data.user_uuid;
         let client_uuid = account_data.client_uuid.read().await.unwrap();
+        let api = BubbleApi::new(
+            account_data.domain.clone(),
+            Some(account_data.bearer.read().await.clone()),
+        );
 
         let mls_provider = MlsProvider::new(account_db.clone());
         let (signature, credential_with_key) =
             get_this_client_mls_resources(user_uuid, &client_uuid, account_db, &mls_provider)
                 .await?;
         let uuid = Uuid::new_v4();
-        let mut group = MlsGroup::new_with_group_id(
+        let mut group = BubbleGroup::new(MlsGroup::new_with_group_id(
             &mls_provider,
             &signature,
             &MLS_GROUP_CONFIG,
             GroupId::from_slice((uuid).as_ref()),
             credential_with_key,
-        )?;
+        )?);
+        // the group is only kept once the server knows it
+        api.create_group(uuid, client_uuid, Vec::new()).await?;
         group.save(&mls_provider)?;
 
         GroupModel {
@@ -116,8 +410,15 @@ impl FrontendInstance {
             uuid,
             name: None,
             image: None,
+            image_attachment: None,
+            image_attachment_clients: None,
             updated_at: NaiveDateTime::default(),
             in_group: true,
+            out_of_sync: false,
+            location_sharing: true,
+            location_paused_until: None,
+            location_precision: Default::default(),
+            location_request_answered_at: None,
             created_at: NaiveDateTime::default(),
         }
         .create(account_db)
@@ -128,6 +429,7 @@ impl FrontendInstance {
 
     #[bridge]
     pub async fn add_member(&self, group_uuid: Uuid, user_uuid: Uuid) -> Result<(), Error> {
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
         let account_db = &global_data.database;
@@ -159,39 +461,16 @@ impl FrontendInstance {
             return Err(Error::NoClientsFound);
         }
 
-        let mut key_packages = Vec::with_capacity(clients.len());
-        let mut client_uuids = Vec::with_capacity(clients.len());
-        for client in &clients {
-            client_uuids.push(client.uuid);
-            let key_package_in = api.request_key_package(&client.uuid).await?;
-            let key_package = key_package_in
-                .validate(mls_provider.crypto(), ProtocolVersion::default())
-                .unwrap();
-            key_packages.push(key_package);
-        }
-
-        let old_members = group
-            .get_group_members()?
-            .into_iter()
-            .filter(|m| m.client_uuid != client_uuid) // we don't want to send the message to ourselves
-            .map(|m| m.client_uuid)
-            .collect::<Vec<_>>();
-
-        let (mls_message_out, welcome_out, _group_info) =
-            group.add_members(&mls_provider, &signature, &key_packages)?;
-        // TODO what happens if we add a member that is already in the group?
-
-        group.merge_pending_commit(&mls_provider).unwrap();
-
-        let mls_message_out = mls_message_out.tls_serialize_detached()?;
-        let welcome_out = welcome_out.tls_serialize_detached()?;
-
-        // we send the welcome message to the new members first, because if it fails, it's easier to recover from
-
-        api.send_message(client_uuids, welcome_out, group_uuid)
-            .await?;
-        api.send_message(old_members, mls_message_out, group_uuid)
-            .await?;
+        add_clients(
+            &mls_provider,
+            &api,
+            &signature,
+            account_db,
+            &mut group,
+            clients.iter().map(|client| client.uuid).collect(),
+            client_uuid,
+        )
+        .await?;
 
         group.save_if_needed(&mls_provider)?;
 
@@ -200,6 +479,7 @@ impl FrontendInstance {
 
     #[bridge]
     pub async fn remove_member(&self, group_uuid: Uuid, user_uuid: Uuid) -> Result<(), Error> {
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
         let account_db = &global_data.database;
@@ -227,33 +507,25 @@ impl FrontendInstance {
                 .ok_or_else(|| Error::MLSGroupLoad)?,
         );
 
-        let members_to_remove = group
-            .get_group_members()?
-            .into_iter()
-            .filter(|m| m.user_uuid == user_uuid)
-            .map(|m| m.index)
-            .collect::<Vec<_>>();
-
-        let (mls_message_out, welcome_out, _group_info) =
-            group.remove_members(&mls_provider, &signature, &members_to_remove)?;
-
-        if welcome_out.is_some() {
-            return Err(Error::UnexpectedWelcome);
-        }
-
-        group.merge_pending_commit(&mls_provider).unwrap();
-
-        group
-            .send_message(&api, &mls_message_out, &[*my_client_uuid])
-            .await?;
-
+        let removed = remove_clients(
+            &mls_provider,
+            &api,
+            &signature,
+            account_db,
+            &mut group,
+            |member| member.user_uuid == user_uuid,
+            *my_client_uuid,
+        )
+        .await;
         group.save_if_needed(&mls_provider)?;
+        removed?;
 
         Ok(())
     }
 
     #[bridge]
     pub async fn leave_group(&self, group_uuid: Uuid) -> Result<(), Error> {
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
         let account_db = &global_data.database;
@@ -283,30 +555,47 @@ impl FrontendInstance {
             .await
             .ok_or_else(|| Error::ReadClientUUID)?;
 
-        // get all clients for our user with the exception of our own client
-        let members_to_remove = group
-            .get_group_members()?
-            .into_iter()
-            .filter(|m| &m.user_uuid == my_user_uuid && &m.client_uuid != my_client_uuid)
-            .map(|m| m.index)
-            .collect::<Vec<_>>();
-
         // remove all members except our own client
+        let mut attempts = 0;
+        loop {
+            // get all clients for our user with the exception of our own client
+            let (members_to_remove, mut exclude): (Vec<_>, Vec<_>) = group
+                .get_group_members()?
+                .into_iter()
+                .filter(|m| &m.user_uuid == my_user_uuid && &m.client_uuid != my_client_uuid)
+                .map(|m| (m.index, m.client_uuid))
+                .unzip();
+            if members_to_remove.is_empty() {
+                break;
+            }
+            exclude.push(*my_client_uuid);
 
-        if !members_to_remove.is_empty() {
             let (mls_message_out, welcome_out, _group_info) =
                 group.remove_members(&mls_provider, &signature, &members_to_remove)?;
 
-            group.merge_pending_commit(&mls_provider).unwrap();
-
             if welcome_out.is_some() {
                 // we do not support proposals so no proposals should exist
                 return Err(Error::UnexpectedWelcome);
             }
 
-            group
-                .send_message(&api, &mls_message_out, &[*my_client_uuid])
-                .await?;
+            if group
+                .send_commit(
+                    &mls_provider,
+                    &api,
+                    &signature,
+                    account_db,
+                    &mls_message_out,
+                    None,
+                    &exclude,
+                )
+                .await?
+            {
+                break;
+            }
+            attempts += 1;
+            if attempts == MAX_COMMIT_ATTEMPTS {
+                return Err(Error::CommitRejected);
+            }
         }
 
         // finally we leave the group for our client
@@ -315,7 +604,14 @@ impl FrontendInstance {
         group.merge_pending_commit(&mls_provider).unwrap();
 
         group
-            .send_message(&api, &leave_message, &[*my_client_uuid])
+            .send_proposal(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &leave_message,
+                &[*my_client_uuid],
+            )
             .await?;
 
         group.save_if_needed(&mls_provider)?;
@@ -326,8 +622,14 @@ impl FrontendInstance {
         Ok(())
     }
 
+    // returns the id of the status, see get_receipts
     #[bridge]
-    pub async fn update_group(&self, group_uuid: Uuid, name: Option<String>) -> Result<(), Error> {
+    pub async fn update_group(
+        &self,
+        group_uuid: Uuid,
+        name: Option<String>,
+    ) -> Result<Uuid, Error> {
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
         let account_db = &global_data.database;
@@ -350,27 +652,43 @@ impl FrontendInstance {
         )
         .await?;
 
-        let message = Message::GroupStatus(GroupStatus {
-            name: name.clone(),
-            image: None,
-        });
+        let mut group_model = GroupModel::from_uuid(account_db, group_uuid)
+            .await?
+            .unwrap();
+        let image = group_model.image.clone();
+        let (id, message) = group_status(
+            &api,
+            &mls_provider,
+            &group,
+            &client_uuid,
+            &mut group_model,
+            name.clone(),
+            image,
+        )
+        .await?;
 
         group
-            .send_application_message(&mls_provider, &api, &signature, &message, &[client_uuid])
+            .send_application_message(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &message,
+                &[client_uuid],
+            )
             .await?;
 
-        let mut group = GroupModel::from_uuid(account_db, group_uuid)
-            .await?
-            .unwrap();
-        group.name = name;
-        group.updated_at = Utc::now().naive_utc();
-        group.update(account_db).await?;
+        group_model.name = name;
+        group_model.updated_at = Utc::now().naive_utc();
+        group_model.update(account_db).await?;
 
-        Ok(())
+        Ok(id)
     }
 
+    // returns the id of the status, see get_receipts
     #[bridge]
-    pub async fn send_group_status(&self, group_uuid: Uuid) -> Result<(), Error> {
+    pub async fn send_group_status(&self, group_uuid: Uuid) -> Result<Uuid, Error> {
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
         let account_db = &global_data.database;
@@ -379,16 +697,11 @@ impl FrontendInstance {
             global_data.domain.clone(),
             Some(global_data.bearer.read().await.clone()),
         );
-        let group = GroupModel::from_uuid(account_db, group_uuid)
+        let mut group_model = GroupModel::from_uuid(account_db, group_uuid)
             .await?
             .unwrap();
         let client_uuid = global_data.client_uuid.read().await.unwrap();
 
-        let message = Message::GroupStatus(GroupStatus {
-            name: group.name,
-            image: group.image.map(Base64),
-        });
-
         let (signature, _) = get_this_client_mls_resources(
             &global_data.user_uuid,
             &client_uuid,
@@ -401,12 +714,319 @@ impl FrontendInstance {
             MlsGroup::load(&GroupId::from_slice(group_uuid.as_ref()), &mls_provider)
                 .ok_or_else(|| Error::MLSGroupLoad)?,
         );
+        let (name, image) = (group_model.name.clone(), group_model.image.clone());
+        let (id, message) = group_status(
+            &api,
+            &mls_provider,
+            &group,
+            &client_uuid,
+            &mut group_model,
+            name,
+            image,
+        )
+        .await?;
 
         group
-            .send_application_message(&mls_provider, &api, &signature, &message, &[client_uuid])
+            .send_application_message(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &message,
+                &[client_uuid],
+            )
             .await?;
 
         group.save_if_needed(&mls_provider)?;
+        group_model.update(account_db).await?;
+
+        Ok(id)
+    }
+
+    // adds the clients that asked to rejoin to our groups that have a member of their user but not the client
+    // the client has to be signed by its user's identity key and the request by the client's key
+    // the clients of the user that no longer exist are removed with it
+    // returns how many groups were changed
+    pub(crate) async fn add_rejoining_clients(
+        &self,
+        requests: HashMap<Uuid, (Uuid, Vec<u8>)>,
+    ) -> Result<usize, Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let my_client_uuid = global_data
+            .client_uuid
+            .read()
+            .await
+            .ok_or_else(|| Error::ReadClientUUID)?;
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &my_client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await?;
+        let resource_fetcher = ResourceFetcher::new(api.clone(), account_db.clone());
+
+        // the clients of the users that asked, the rejoining clients are among them
+        let mut user_clients: HashMap<Uuid, HashSet<Uuid>> = HashMap::new();
+        let mut rejoining = HashMap::with_capacity(requests.len());
+        for (client_uuid, (user_uuid, signature_key)) in requests {
+            let clients = match user_clients.entry(user_uuid) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => match resource_fetcher
+                    .get_clients_full_authentication(&user_uuid)
+                    .await
+                {
+                    Ok(clients) => {
+                        let authenticated = clients.iter().any(|client| {
+                            client.uuid == client_uuid && client.signing_key.0 == signature_key
+                        });
+                        if !authenticated {
+                            warn!("ignoring the rejoin request of client {}", client_uuid);
+                            continue;
+                        }
+                        entry.insert(clients.into_iter().map(|client| client.uuid).collect())
+                    }
+                    Err(e) => {
+                        warn!("unable to fetch the clients of user {}: {}", user_uuid, e);
+                        continue;
+                    }
+                },
+            };
+            if clients.contains(&client_uuid) {
+                rejoining.insert(client_uuid, user_uuid);
+            }
+        }
+        if rejoining.is_empty() {
+            return Ok(0);
+        }
+
+        let mut changed = 0;
+        for group_model in GroupModel::all_in_group(account_db).await? {
+            // our state is behind the group's, we can't commit until we're added again
+            if group_model.out_of_sync {
+                continue;
+            }
+            let mut group = BubbleGroup::new_from_uuid(&group_model.uuid, &mls_provider)
+                .ok_or_else(|| Error::MLSGroupLoad)?;
+            let members = group.get_group_members()?;
+            let member_clients: HashSet<_> =
+                members.iter().map(|member| member.client_uuid).collect();
+            let users: HashSet<_> = members.iter().map(|member| member.user_uuid).collect();
+            let missing: Vec<_> = rejoining
+                .iter()
+                .filter(|(client_uuid, user_uuid)| {
+                    users.contains(user_uuid) && !member_clients.contains(client_uuid)
+                })
+                .map(|(client_uuid, _)| *client_uuid)
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+
+            // the client a device had before it was restored doesn't exist anymore, the server rejects commits sent to it
+            let mut added = remove_clients(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &mut group,
+                |member| {
+                    user_clients
+                        .get(&member.user_uuid)
+                        .is_some_and(|clients| !clients.contains(&member.client_uuid))
+                },
+                my_client_uuid,
+            )
+            .await;
+            if added.is_ok() {
+                added = add_clients(
+                    &mls_provider,
+                    &api,
+                    &signature,
+                    account_db,
+                    &mut group,
+                    missing,
+                    my_client_uuid,
+                )
+                .await;
+            }
+            group.save_if_needed(&mls_provider)?;
+            match added {
+                Ok(()) => changed += 1,
+                Err(e) => warn!("unable to add clients to group {}: {}", group_model.uuid, e),
+            }
+        }
+        Ok(changed)
+    }
+
+    // the group image of a status is downloaded once the inbox is processed, without holding the process lock
+    // it's dropped if another status replaced the attachment in the meantime
+    pub(crate) async fn download_group_image(
+        &self,
+        group_uuid: Uuid,
+        attachment: Attachment,
+    ) -> Result<(), Error> {
+        let image = {
+            let global = self.account_data.read().await;
+            let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+            let mls_provider = MlsProvider::new(global_data.database.clone());
+            let api = BubbleApi::new(
+                global_data.domain.clone(),
+                Some(global_data.bearer.read().await.clone()),
+            );
+            let client_uuid = global_data
+                .client_uuid
+                .read()
+                .await
+                .ok_or(Error::ReadClientUUID)?;
+            download_attachment(&api, &mls_provider, &attachment, client_uuid).await
+        };
+
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let mut group_model = GroupModel::from_uuid(account_db, group_uuid)
+            .await?
+            .ok_or_else(|| Error::GroupNotFound)?;
+        if group_model.image_attachment.as_ref().map(|a| &a.hash.0) != Some(&attachment.hash.0) {
+            return Ok(());
+        }
+        let downloaded = match image {
+            Ok(image) => {
+                group_model.image = Some(image);
+                Ok(())
+            }
+            // we keep the image we have, the next status with this attachment tries again
+            Err(e) => {
+                group_model.image_attachment = None;
+                group_model.image_attachment_clients = None;
+                Err(e)
+            }
+        };
+        group_model.update(account_db).await?;
+        downloaded
+    }
+
+    // null removes the image, returns the id of the status
+    #[bridge]
+    pub async fn set_group_image(
+        &self,
+        group_uuid: Uuid,
+        image: Option<Base64>,
+    ) -> Result<Uuid, Error> {
+        let image = image.map(|image| image.0);
+        if let Some(image) = &image {
+            check_image(image)?;
+        }
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let mut group = BubbleGroup::new(
+            MlsGroup::load(&GroupId::from_slice(group_uuid.as_ref()), &mls_provider)
+                .ok_or_else(|| Error::MLSGroupLoad)?,
+        );
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let client_uuid = global_data.client_uuid.read().await.unwrap();
+
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await?;
+
+        let mut group_model = GroupModel::from_uuid(account_db, group_uuid)
+            .await?
+            .ok_or_else(|| Error::GroupNotFound)?;
+        let name = group_model.name.clone();
+        let (id, message) = group_status(
+            &api,
+            &mls_provider,
+            &group,
+            &client_uuid,
+            &mut group_model,
+            name,
+            image.clone(),
+        )
+        .await?;
+
+        group
+            .send_application_message(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &message,
+                &[client_uuid],
+            )
+            .await?;
+
+        group_model.image = image;
+        group_model.updated_at = Utc::now().naive_utc();
+        group_model.update(account_db).await?;
+
+        Ok(id)
+    }
+
+    #[bridge]
+    pub async fn get_location_settings(&self, group_uuid: Uuid) -> Result<LocationSettings, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let group = GroupModel::from_uuid(account_db, group_uuid)
+            .await?
+            .ok_or_else(|| Error::GroupNotFound)?;
+
+        Ok(LocationSettings {
+            enabled: group.location_sharing,
+            paused_until: group
+                .location_paused_until
+                .map(|paused_until| paused_until.timestamp_millis()),
+            precision: group.location_precision,
+        })
+    }
+
+    #[bridge]
+    pub async fn set_location_settings(
+        &self,
+        group_uuid: Uuid,
+        settings: LocationSettings,
+    ) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let mut group = GroupModel::from_uuid(account_db, group_uuid)
+            .await?
+            .ok_or_else(|| Error::GroupNotFound)?;
+
+        settings.precision.check()?;
+        let paused_until = match settings.paused_until {
+            Some(paused_until) => match NaiveDateTime::from_timestamp_millis(paused_until) {
+                Some(paused_until) if paused_until > Utc::now().naive_utc() => Some(paused_until),
+                _ => return Err(Error::InvalidPausedUntil),
+            },
+            None => None,
+        };
+
+        group.location_sharing = settings.enabled;
+        group.location_paused_until = paused_until;
+        group.location_precision = settings.precision;
+        group.update(account_db).await?;
 
         Ok(())
     }
@@ -1,16 +1,40 @@
 use crate::api::BubbleApi;
-use crate::application_message::{Location, Message};
+use crate::application_message::{
+    Location, LocationBatch, LocationRequest, LocationUpdate, Message,
+};
 use crate::helper::bubble_group::BubbleGroup;
 use crate::helper::helper::get_this_client_mls_resources;
+use crate::helper::location_export::ExportFormat;
+use crate::helper::location_precision::apply_precision;
 use crate::js_interface::FrontendInstance;
 use crate::mls_provider::MlsProvider;
+use crate::models::account::group::Group as GroupModel;
 use crate::models::account::location::Location as LocationModel;
+use crate::public::native_api::NativeApi;
+use crate::Error;
 use bridge_macro::bridge;
+use log::warn;
 use openmls::group::MlsGroup;
 use openmls::prelude::GroupId;
-use sqlx::types::chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+// we reply to at most one location request per group in this many milliseconds
+const LOCATION_REQUEST_INTERVAL: i64 = 60 * 1000;
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MemberLocation {
+    pub user_uuid: Uuid,
+    // null if the user isn't cached yet
+    pub username: Option<String>,
+    pub name: Option<String>,
+    pub client_uuid: Uuid,
+    pub location: Location,
+}
+
 impl FrontendInstance {
     #[bridge]
     pub async fn get_location(
@@ -27,12 +51,40 @@ impl FrontendInstance {
             .await
             .unwrap();
 
-        Ok(locations
+        Ok(locations.into_iter().map(Location::from).collect())
+    }
+
+    // the most recent location of every client in the group
+    #[bridge]
+    pub async fn get_latest_locations(
+        &self,
+        group_uuid: Uuid,
+    ) -> Result<Vec<MemberLocation>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let group = BubbleGroup::new_from_uuid(&group_uuid, &mls_provider)
+            .ok_or_else(|| Error::MLSGroupLoad)?;
+        // clients that left the group still have locations
+        let members: HashMap<_, _> = group
+            .get_group_members()?
+            .into_iter()
+            .map(|member| (member.client_uuid, member.user_uuid))
+            .collect();
+
+        Ok(LocationModel::latest(account_db, &group_uuid)
+            .await?
             .into_iter()
-            .map(|location| Location {
-                longitude: location.longitude,
-                latitude: location.latitude,
-                timestamp: location.location_date.timestamp_millis(),
+            .filter_map(|latest| {
+                let client_uuid = latest.location.client_uuid;
+                Some(MemberLocation {
+                    user_uuid: *members.get(&client_uuid)?,
+                    username: latest.username,
+                    name: latest.name,
+                    client_uuid,
+                    location: latest.location.into(),
+                })
             })
             .collect())
     }
@@ -62,14 +114,49 @@ impl FrontendInstance {
         Ok(locations)
     }
 
+    // the locations of the client in the range as a "gpx" or "geojson" document
+    #[bridge]
+    pub async fn export_locations(
+        &self,
+        group_uuid: Uuid,
+        client: Uuid,
+        from_timestamp: i64,
+        to_timestamp: i64,
+        format: String,
+    ) -> Result<String, Error> {
+        let export_format =
+            ExportFormat::parse(&format).ok_or_else(|| Error::UnknownExportFormat(format))?;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let from = NaiveDateTime::from_timestamp_millis(from_timestamp).unwrap();
+        let to = NaiveDateTime::from_timestamp_millis(to_timestamp).unwrap();
+        let locations =
+            LocationModel::range(&global_data.database, &group_uuid, &client, &from, &to).await?;
+
+        Ok(export_format.export(&client, &locations))
+    }
+
     #[bridge]
     pub async fn send_location(
         &self,
         group_uuid: Uuid,
-        longitude: f64,
-        latitude: f64,
-        timestamp: i64,
-    ) -> Result<(), ()> {
+        location: Location,
+    ) -> Result<Option<Uuid>, ()> {
+        self.send_locations(group_uuid, vec![location]).await
+    }
+
+    // encrypts and sends the locations in a single message
+    // returns the id the members acknowledge it with, null if we don't share our location with the group
+    #[bridge]
+    pub async fn send_locations(
+        &self,
+        group_uuid: Uuid,
+        locations: Vec<Location>,
+    ) -> Result<Option<Uuid>, ()> {
+        if locations.is_empty() {
+            return Ok(None);
+        }
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().unwrap();
         let account_db = &global_data.database;
@@ -85,22 +172,167 @@ impl FrontendInstance {
                 .await
                 .unwrap();
 
+        let group_model = GroupModel::from_uuid(account_db, group_uuid)
+            .await
+            .unwrap()
+            .unwrap();
+        if !group_model.shares_location(Utc::now().naive_utc()) {
+            return Ok(None);
+        }
+
         let mut group = BubbleGroup::new(
             MlsGroup::load(&GroupId::from_slice(group_uuid.as_ref()), &mls_provider).unwrap(),
         );
 
-        let message = Message::Location(Location {
-            longitude,
-            latitude,
-            timestamp,
-        });
+        let mut locations = locations;
+        for location in &mut locations {
+            apply_precision(location, group_model.location_precision);
+        }
+
+        // a single location isn't batched so clients that don't know batches can still read it
+        let id = Uuid::new_v4();
+        let message = if locations.len() == 1 {
+            Message::Location(LocationUpdate {
+                id: Some(id),
+                location: locations.pop().unwrap(),
+            })
+        } else {
+            Message::LocationBatch(LocationBatch {
+                id: Some(id),
+                locations,
+            })
+        };
 
         group
-            .send_application_message(&mls_provider, &api, &signature, &message, &[*client_uuid])
+            .send_application_message(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &message,
+                &[*client_uuid],
+            )
             .await
             .unwrap();
 
         group.save_if_needed(&mls_provider).unwrap();
+        Ok(Some(id))
+    }
+
+    // asks the member, or everyone if null, to send their current location to the group
+    #[bridge]
+    pub async fn request_member_location(
+        &self,
+        group_uuid: Uuid,
+        user_uuid: Option<Uuid>,
+    ) -> Result<(), Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let client_uuid = global_data.client_uuid.read().await.unwrap();
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await?;
+        let mut group = BubbleGroup::new_from_uuid(&group_uuid, &mls_provider)
+            .ok_or_else(|| Error::MLSGroupLoad)?;
+
+        group
+            .send_application_message(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &Message::LocationRequest(LocationRequest { user_uuid }),
+                &[client_uuid],
+            )
+            .await?;
+        group.save_if_needed(&mls_provider)?;
+        Ok(())
+    }
+
+    // replies to the groups that asked for our location with a fresh fix
+    // groups we don't share our location with or that we replied to recently are skipped
+    pub(crate) async fn answer_location_requests(
+        &self,
+        group_uuids: HashSet<Uuid>,
+    ) -> Result<(), Error> {
+        let groups = {
+            let global = self.account_data.read().await;
+            let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+            let account_db = &global_data.database;
+            let now = Utc::now().naive_utc();
+            let mut groups = Vec::with_capacity(group_uuids.len());
+            for group_uuid in group_uuids {
+                let Some(group) = GroupModel::from_uuid(account_db, group_uuid).await? else {
+                    continue;
+                };
+                let answered_recently =
+                    group.location_request_answered_at.is_some_and(|answered| {
+                        (now - answered).num_milliseconds() < LOCATION_REQUEST_INTERVAL
+                    });
+                if !group.shares_location(now) || answered_recently {
+                    continue;
+                }
+                groups.push(group_uuid);
+            }
+            groups
+        };
+        if groups.is_empty() || !self.device_api.has_location_permissions().unwrap_or(false) {
+            return Ok(());
+        }
+
+        // getting a fix can take a while
+        let location = tokio::task::block_in_place(|| self.device_api.request_location());
+        let Ok(Some(location)) = location else {
+            warn!("no location to answer location requests with");
+            return Ok(());
+        };
+        for group_uuid in groups {
+            match self
+                .send_locations(group_uuid, vec![location.clone()])
+                .await
+            {
+                // only a request we answered counts towards the rate limit
+                Ok(Some(_)) => self.location_request_answered(group_uuid).await?,
+                Ok(None) => {}
+                Err(_) => warn!(
+                    "unable to answer location request of group {:?}",
+                    group_uuid
+                ),
+            }
+        }
         Ok(())
     }
+
+    async fn location_request_answered(&self, group_uuid: Uuid) -> Result<(), Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let Some(mut group) = GroupModel::from_uuid(account_db, group_uuid).await? else {
+            return Ok(());
+        };
+        group.location_request_answered_at = Some(Utc::now().naive_utc());
+        group.update(account_db).await?;
+        Ok(())
+    }
+
+    // the groups send_locations currently sends to
+    pub async fn get_location_sharing_groups(&self) -> Result<Vec<Uuid>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let groups =
+            GroupModel::all_sharing_location(&global_data.database, Utc::now().naive_utc()).await?;
+        Ok(groups.into_iter().map(|group| group.uuid).collect())
+    }
 }
@@ -1,29 +1,44 @@
 use crate::api::BubbleApi;
-use crate::application_message::Message;
+use crate::application_message::{Attachment, Message};
 use crate::helper::bubble_group::BubbleGroup;
+use crate::helper::geofence::record_place_events;
 use crate::helper::helper::{get_this_client_mls_resources, parse_identity};
+use crate::js_interface::receipt::{send_receipt, ReceiptSettings};
+use crate::js_interface::retention::RetentionSettings;
 use crate::js_interface::FrontendInstance;
 use crate::mls_provider::MlsProvider;
+use crate::models::account::chat_message::ChatMessage;
 use crate::models::account::group::Group;
 use crate::models::account::inbox::Inbox;
 use crate::models::account::location::Location;
-use crate::types::MLS_GROUP_CONFIG;
+use crate::models::account::location_segment::LocationSegment;
+use crate::models::account::outbox::Outbox;
+use crate::models::account::receipt::Receipt;
+use crate::types::{DbPool, MLS_GROUP_CONFIG};
 use crate::Error;
 use log::warn;
 use openmls::prelude::*;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use sqlx::types::chrono::{NaiveDateTime, Utc};
-
-use crate::models::kv::AccountKv;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
 
 use bridge_macro::bridge;
 use uuid::Uuid;
 
+const STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// a queued message that failed permanently or too many times, the messages after it were still sent
+#[bridge]
 #[derive(Serialize, Deserialize, Debug)]
-struct WaitingStagedCommit {
-    pub commit_hash: Vec<u8>,
-    pub commit_message_hash: Vec<u8>,
+pub struct DroppedMessage {
+    pub id: i32,
+    pub group_uuid: Uuid,
+    // application, commit, proposal or welcome
+    pub kind: String,
+    pub error: String,
+    pub timestamp: i64,
 }
 
 fn print_message(message: &Inbox) {
@@ -53,6 +68,102 @@ fn print_message(message: &Inbox) {
 impl FrontendInstance {
     #[bridge]
     pub async fn receive_messages(&self) -> Result<usize, Error> {
+        let received = self.fetch_messages().await?;
+        self.process_messages().await;
+        self.sync_clients_if_due().await;
+        self.flush_outbox().await?;
+        Ok(received.len())
+    }
+
+    // location requests are answered, group images downloaded and rejoining clients added once the inbox is processed
+    // they all take the process lock
+    async fn process_messages(&self) {
+        let (location_requests, group_images, rejoin_requests) = self.process_inbox().await;
+        for (group_uuid, attachment) in group_images {
+            if let Err(e) = self.download_group_image(group_uuid, attachment).await {
+                warn!("failed to download group image: {}", e);
+            }
+        }
+        if !rejoin_requests.is_empty() {
+            if let Err(e) = self.add_rejoining_clients(rejoin_requests).await {
+                warn!("unable to add rejoining clients: {}", e);
+            }
+        }
+        if location_requests.is_empty() {
+            return;
+        }
+        if let Err(e) = self.answer_location_requests(location_requests).await {
+            warn!("unable to answer location requests: {}", e);
+        }
+    }
+
+    // sends the queued messages of every group that is due for another attempt
+    #[bridge]
+    pub async fn flush_outbox(&self) -> Result<(), Error> {
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or(Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let client_uuid = global_data
+            .client_uuid
+            .read()
+            .await
+            .ok_or(Error::ReadClientUUID)?;
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await?;
+
+        for group_uuid in Outbox::due_group_uuids(account_db, Utc::now().naive_utc()).await? {
+            let mut group = BubbleGroup::new_from_uuid(&group_uuid, &mls_provider)
+                .ok_or(Error::MLSGroupLoad)?;
+            if let Err(e) = group
+                .flush_outbox(&mls_provider, &api, &signature, account_db)
+                .await
+            {
+                warn!("unable to flush outbox of group {:?}: {}", group_uuid, e);
+            }
+            group.save_if_needed(&mls_provider)?;
+        }
+        Ok(())
+    }
+
+    // the messages that were given up on, they stay listed until they are dismissed
+    #[bridge]
+    pub async fn get_dropped_messages(&self) -> Result<Vec<DroppedMessage>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or(Error::NoGlobalAccountData)?;
+        Ok(Outbox::all_dropped(&global_data.database)
+            .await?
+            .into_iter()
+            .map(|dropped| DroppedMessage {
+                id: dropped.id,
+                group_uuid: dropped.group_uuid,
+                kind: dropped.kind.as_str().to_string(),
+                error: dropped.error.unwrap_or_default(),
+                timestamp: dropped.dropped_date.unwrap_or_default().timestamp_millis(),
+            })
+            .collect())
+    }
+
+    #[bridge]
+    pub async fn dismiss_dropped_message(&self, id: i32) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or(Error::NoGlobalAccountData)?;
+        Outbox::delete_dropped_by_id(&global_data.database, id).await?;
+        Ok(())
+    }
+
+    // stores every message waiting on the server in the inbox and acknowledges them, returning their server ids
+    async fn fetch_messages(&self) -> Result<HashSet<i32>, Error> {
         let global = self.account_data.read().await;
         let global_data = global.as_ref().unwrap();
         let account_db = &global_data.database;
@@ -61,25 +172,127 @@ impl FrontendInstance {
             global_data.domain.clone(),
             Some(global_data.bearer.read().await.clone()),
         );
-        let messages = api.receive_messages(*my_client_uuid).await.unwrap();
-        let num_received = messages.len();
-        for message in messages {
-            let mut inbox = Inbox {
-                id: 0,
-                message: message.message.0,
-                server_received_date: NaiveDateTime::from_timestamp_millis(message.received_date)
-                    .unwrap(),
-                received_date: Utc::now().naive_utc(),
-            };
-            print_message(&inbox);
-            inbox.create(account_db).await.unwrap();
+        let mut received = HashSet::new();
+        let mut cursor = None;
+        loop {
+            let page = api.receive_messages(*my_client_uuid, cursor).await.unwrap();
+            let mut page_ids = Vec::with_capacity(page.messages.len());
+            for message in page.messages {
+                page_ids.push(message.id);
+                let mut inbox = Inbox::from(message);
+                print_message(&inbox);
+                inbox.create(account_db).await.unwrap();
+            }
+            let mut expired_ids = Vec::with_capacity(page.expired.len());
+            for expired in page.expired {
+                warn!(
+                    "{} messages for group {:?} expired before we received them",
+                    expired.amount, expired.group_uuid
+                );
+                expired_ids.push(expired.id);
+                let group = match expired.group_uuid {
+                    Some(group_uuid) => Group::from_uuid(account_db, group_uuid).await?,
+                    None => None,
+                };
+                if let Some(mut group) = group {
+                    group.out_of_sync = true;
+                    group.update(account_db).await?;
+                }
+            }
+            if !page_ids.is_empty() || !expired_ids.is_empty() {
+                received.extend(&page_ids);
+                api.ack_messages(*my_client_uuid, page_ids, expired_ids)
+                    .await?;
+            }
+            if !page.has_more {
+                break;
+            }
+            cursor = page.next_cursor;
         }
+        Ok(received)
+    }
 
+    #[bridge]
+    pub async fn subscribe_to_messages(self: &Arc<Self>) -> Result<(), Error> {
+        if !self.logged_in().await {
+            return Err(Error::NoGlobalAccountData);
+        }
+        let mut message_stream = self.message_stream.lock().unwrap();
+        if message_stream
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+        {
+            return Ok(());
+        }
+        let instance = self.clone();
+        *message_stream = Some(self.static_data.tokio.handle.spawn(async move {
+            loop {
+                // reconnecting is a good time to retry whatever we failed to send
+                if let Err(e) = instance.flush_outbox().await {
+                    warn!("outbox error: {}", e);
+                }
+                if let Err(e) = instance.stream_messages().await {
+                    warn!("message stream error: {}", e);
+                }
+                tokio::time::sleep(STREAM_RECONNECT_DELAY).await;
+            }
+        }));
+        Ok(())
+    }
+
+    #[bridge]
+    pub async fn unsubscribe_from_messages(&self) -> Result<(), Error> {
+        if let Some(handle) = self.message_stream.lock().unwrap().take() {
+            handle.abort();
+        }
+        Ok(())
+    }
+
+    async fn stream_messages(&self) -> Result<(), Error> {
+        let (api, my_client_uuid, account_db) = {
+            let global = self.account_data.read().await;
+            let global_data = global.as_ref().ok_or(Error::NoGlobalAccountData)?;
+            let my_client_uuid = global_data
+                .client_uuid
+                .read()
+                .await
+                .ok_or(Error::ReadClientUUID)?;
+            let api = BubbleApi::new(
+                global_data.domain.clone(),
+                Some(global_data.bearer.read().await.clone()),
+            );
+            (api, my_client_uuid, global_data.database.clone())
+        };
+        let mut stream = api.stream_messages(my_client_uuid).await?;
+        // anything sent before the stream was opened is still waiting on the server
+        let fetched = self.fetch_messages().await?;
         self.process_messages().await;
-        Ok(num_received)
+        while let Some(message) = stream.next().await? {
+            // sent after the stream was opened but already fetched above
+            if fetched.contains(&message.id) {
+                continue;
+            }
+            let message_id = message.id;
+            let mut inbox = Inbox::from(message);
+            print_message(&inbox);
+            inbox.create(&account_db).await?;
+            api.ack_messages(my_client_uuid, vec![message_id], Vec::new())
+                .await?;
+            self.process_messages().await;
+        }
+        Ok(())
     }
 
-    async fn process_messages(&self) {
+    // returns the groups that asked us for our location, the group images to download
+    // and the clients that asked to rejoin our groups with their user and signature key
+    async fn process_inbox(
+        &self,
+    ) -> (
+        HashSet<Uuid>,
+        HashMap<Uuid, Attachment>,
+        HashMap<Uuid, (Uuid, Vec<u8>)>,
+    ) {
+        let _processing = self.process_lock.lock().await;
         let global = self.account_data.read().await;
         let global_data = global.as_ref().unwrap();
         let account_db = &global_data.database;
@@ -96,19 +309,36 @@ impl FrontendInstance {
             }
             out
         };
+        // the ids of the messages we received per group, acknowledged together once everything is processed
+        let mut receipts: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        let mut location_requests = HashSet::new();
+        let mut group_images = HashMap::new();
+        let mut rejoin_requests = HashMap::new();
         for (message, inbox_message) in messages {
             print_message(&inbox_message);
             let body = message.extract();
             match body {
                 MlsMessageInBody::PublicMessage(m) => {
-                    self.process_group_message(&inbox_message, m.into())
-                        .await
-                        .unwrap();
+                    self.process_group_message(
+                        &inbox_message,
+                        m.into(),
+                        &mut receipts,
+                        &mut location_requests,
+                        &mut group_images,
+                    )
+                    .await
+                    .unwrap();
                 }
                 MlsMessageInBody::PrivateMessage(m) => {
-                    self.process_group_message(&inbox_message, m.into())
-                        .await
-                        .unwrap();
+                    self.process_group_message(
+                        &inbox_message,
+                        m.into(),
+                        &mut receipts,
+                        &mut location_requests,
+                        &mut group_images,
+                    )
+                    .await
+                    .unwrap();
                 }
                 MlsMessageInBody::Welcome(welcome) => {
                     let mut group = BubbleGroup::new(
@@ -119,17 +349,34 @@ impl FrontendInstance {
 
                     let exists = Group::from_uuid(account_db, group_id).await.unwrap();
 
-                    if let Some(group) = exists {
-                        group.delete(account_db).await.unwrap();
-                    }
+                    // rejoining a group keeps our location sharing settings
+                    let (location_sharing, location_paused_until, location_precision) = match exists
+                    {
+                        Some(group) => {
+                            group.delete(account_db).await.unwrap();
+                            (
+                                group.location_sharing,
+                                group.location_paused_until,
+                                group.location_precision,
+                            )
+                        }
+                        None => (true, None, Default::default()),
+                    };
 
                     Group {
                         id: 0,
                         uuid: group_id,
                         name: None,
                         image: None,
+                        image_attachment: None,
+                        image_attachment_clients: None,
                         updated_at: NaiveDateTime::default(),
                         in_group: true,
+                        out_of_sync: false,
+                        location_sharing,
+                        location_paused_until,
+                        location_precision,
+                        location_request_answered_at: None,
                         created_at: NaiveDateTime::default(),
                     }
                     .create(account_db)
@@ -139,7 +386,23 @@ impl FrontendInstance {
                     group.save_if_needed(&mls_provider).unwrap();
                 }
                 MlsMessageInBody::GroupInfo(_) => continue,
-                MlsMessageInBody::KeyPackage(_) => continue,
+                // a client asking to be added to our groups again, e.g. a device restored from a backup
+                MlsMessageInBody::KeyPackage(key_package) => {
+                    match key_package.validate(mls_provider.crypto(), ProtocolVersion::default()) {
+                        Ok(key_package) => {
+                            let leaf_node = key_package.leaf_node();
+                            match parse_identity(leaf_node.credential().identity()) {
+                                Ok((user_uuid, client_uuid)) => {
+                                    let signature_key = leaf_node.signature_key().as_slice();
+                                    rejoin_requests
+                                        .insert(client_uuid, (user_uuid, signature_key.to_vec()));
+                                }
+                                Err(e) => warn!("invalid rejoin request: {}", e),
+                            }
+                        }
+                        Err(e) => warn!("invalid rejoin request: {:?}", e),
+                    }
+                }
             }
 
             // TODO: process message
@@ -148,12 +411,54 @@ impl FrontendInstance {
                 .await
                 .unwrap();
         }
+
+        if receipts.is_empty()
+            || !ReceiptSettings::load(account_db)
+                .await
+                .unwrap()
+                .send_delivery_receipts
+        {
+            return (location_requests, group_images, rejoin_requests);
+        }
+        let client_uuid = global_data.client_uuid.read().await.unwrap();
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await
+        .unwrap();
+        for (group_uuid, ids) in receipts {
+            if let Err(e) = send_receipt(
+                &mls_provider,
+                &api,
+                &signature,
+                account_db,
+                &client_uuid,
+                &group_uuid,
+                ids,
+                false,
+            )
+            .await
+            {
+                warn!("unable to send receipt to group {:?}: {}", group_uuid, e);
+            }
+        }
+        (location_requests, group_images, rejoin_requests)
     }
 
     async fn process_group_message(
         &self,
         inbox_message: &Inbox,
         message: ProtocolMessage,
+        receipts: &mut HashMap<Uuid, Vec<Uuid>>,
+        location_requests: &mut HashSet<Uuid>,
+        group_images: &mut HashMap<Uuid, Attachment>,
     ) -> Result<(), ()> {
         warn!("processing group message: id: {}", inbox_message.id);
         let global = self.account_data.read().await;
@@ -161,6 +466,7 @@ impl FrontendInstance {
         let account_db = &global_data.database;
         let mls_provider = MlsProvider::new(account_db.clone());
         let user_uuid = &global_data.user_uuid;
+        let my_client_uuid = global_data.client_uuid.read().await.unwrap();
         let api = BubbleApi::new(
             global_data.domain.clone(),
             Some(global_data.bearer.read().await.clone()),
@@ -181,30 +487,6 @@ impl FrontendInstance {
             return Ok(());
         }
 
-        if let Some(pending_commit) = group.pending_commit() {
-            // we need to check if this is our own message
-            // retrieve the last commit from the db
-            let last_commit = AccountKv::get(
-                account_db,
-                &format!("waiting_staged_commit_{}", group.group_uuid()),
-            )
-            .await
-            .unwrap();
-            if let Some(last_commit) = last_commit {
-                let last_commit: WaitingStagedCommit = serde_json::from_str(&last_commit).unwrap();
-                // first we hash the raw message
-                let commit_hash =
-                    Sha256::digest(serde_json::to_vec(&pending_commit).unwrap()).to_vec();
-                let commit_message_hash = Sha256::digest(&inbox_message.message).to_vec();
-                if last_commit.commit_hash == commit_hash
-                    && last_commit.commit_message_hash == commit_message_hash
-                {
-                    group.merge_pending_commit(&mls_provider).unwrap();
-                    group.save_if_needed(&mls_provider).unwrap();
-                    return Ok(());
-                }
-            }
-        }
         let group_message = group
             .process_message(&mls_provider, message)
             .map_err(|e| {
@@ -216,26 +498,93 @@ impl FrontendInstance {
         let content = group_message.into_content();
         match content {
             ProcessedMessageContent::ApplicationMessage(app) => {
-                let message: Message = serde_json::from_slice(&app.into_bytes()).unwrap();
+                // the content comes from another client, a message we can't read mustn't stop the inbox
+                let message = match serde_json::from_slice(&app.into_bytes()) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("unable to decode application message: {}", e);
+                        Message::Unknown
+                    }
+                };
                 warn!("application: message: {:?}", message);
                 match message {
-                    Message::Location(message) => {
-                        Location {
-                            id: 0,
-                            client_uuid,
-                            group_uuid: group.group_uuid(),
-                            longitude: message.longitude,
-                            latitude: message.latitude,
-                            location_date: NaiveDateTime::from_timestamp_millis(message.timestamp)
-                                .unwrap(),
-                            raw: inbox_message.message.clone(),
-                            created_date: Default::default(),
-                        }
-                        .create(account_db)
+                    Message::Location(update) => {
+                        store_location(
+                            account_db,
+                            Location::new(
+                                client_uuid,
+                                group.group_uuid(),
+                                update.location,
+                                Some(inbox_message.message.clone()),
+                            ),
+                        )
                         .await
                         .unwrap();
+                        if let Some(id) = update.id {
+                            receipts.entry(group.group_uuid()).or_default().push(id);
+                        }
+                    }
+                    Message::LocationBatch(batch) => {
+                        if let Some(id) = batch.id {
+                            receipts.entry(group.group_uuid()).or_default().push(id);
+                        }
+                        // the batch is kept once, with its first location
+                        let mut raw = Some(inbox_message.message.clone());
+                        for message in batch.locations {
+                            store_location(
+                                account_db,
+                                Location::new(client_uuid, group.group_uuid(), message, raw.take()),
+                            )
+                            .await
+                            .unwrap();
+                        }
+                    }
+                    Message::Text(text) => {
+                        ChatMessage::new(client_uuid, group.group_uuid(), &text)
+                            .create(account_db)
+                            .await
+                            .unwrap();
+                        receipts
+                            .entry(group.group_uuid())
+                            .or_default()
+                            .push(text.id);
+                    }
+                    Message::LocationRequest(request) => {
+                        if request.user_uuid.is_none_or(|uuid| uuid == *user_uuid) {
+                            location_requests.insert(group.group_uuid());
+                        }
+                    }
+                    // the timestamp is the sender's, a value that isn't a date is ignored
+                    Message::Receipt(receipt) => {
+                        match NaiveDateTime::from_timestamp_millis(receipt.timestamp) {
+                            Some(date) => {
+                                for id in receipt.ids {
+                                    Receipt::record(
+                                        account_db,
+                                        &group.group_uuid(),
+                                        &id,
+                                        &client_uuid,
+                                        &date,
+                                        receipt.read,
+                                    )
+                                    .await
+                                    .unwrap();
+                                }
+                            }
+                            None => warn!(
+                                "skipping receipt with invalid timestamp {}",
+                                receipt.timestamp
+                            ),
+                        }
+                    }
+                    Message::Unknown => {
+                        warn!("skipping application message of an unknown type");
                     }
                     Message::GroupStatus(status) => {
+                        // an outdated status is still acknowledged
+                        if let Some(id) = status.id {
+                            receipts.entry(group.group_uuid()).or_default().push(id);
+                        }
                         let mut group = Group::from_uuid(account_db, group.group_uuid())
                             .await
                             .unwrap()
@@ -246,7 +595,23 @@ impl FrontendInstance {
                         );
                         if inbox_message.server_received_date > group.updated_at {
                             group.name = status.name;
-                            group.image = status.image.map(|i| i.0);
+                            match status.image_attachment {
+                                // the members that already have this image don't download it again
+                                Some(attachment)
+                                    if group.image_attachment.as_ref().map(|a| &a.hash.0)
+                                        == Some(&attachment.hash.0) => {}
+                                Some(attachment) => {
+                                    group.image_attachment = Some(attachment.clone());
+                                    group.image_attachment_clients = None;
+                                    group_images.insert(group.uuid, attachment);
+                                }
+                                None => {
+                                    group.image = status.image.map(|i| i.0);
+                                    group.image_attachment = None;
+                                    group.image_attachment_clients = None;
+                                    group_images.remove(&group.uuid);
+                                }
+                            }
                             group.updated_at = Utc::now().naive_utc();
                             group.update(account_db).await.unwrap();
                         }
@@ -263,49 +628,38 @@ impl FrontendInstance {
                 .await
                 .unwrap();
 
-                // whenever we receive a proposal, we store it in the pending proposals, commit, and then send the commit to the group including ourselves
-                // we wait for the DS to send it back to us before merging
+                // whenever we receive a proposal, we store it in the pending proposals, commit, and then send the commit to the group
+                // every member does this, the server only accepts the first commit and the others merge it instead
                 group.store_pending_proposal(*m);
 
                 let (commit, _welcome, _group_info) = group
                     .commit_to_pending_proposals(&mls_provider, &signature)
                     .unwrap();
 
-                // the above should have created a pending commit
-                // let's retrieve it
                 let staged = group.pending_commit().unwrap();
-
-                // hash the commit
-                let commit_hash = Sha256::digest(serde_json::to_vec(staged).unwrap()).to_vec();
-                // hash the commit message
-                let commit_message_hash =
-                    Sha256::digest(commit.tls_serialize_detached().unwrap()).to_vec();
-                let waiting = WaitingStagedCommit {
-                    commit_hash,
-                    commit_message_hash,
-                };
-
                 let members = group.get_group_members().unwrap();
-                let removed_client_uuids = staged
+                let mut exclude = staged
                     .remove_proposals()
                     .map(|p| p.remove_proposal().removed())
                     .map(|i| members.iter().find(|m| m.index == i).unwrap().client_uuid)
                     .collect::<Vec<_>>();
+                exclude.push(my_client_uuid);
 
-                // store them in the db, keyed by the group uuid
-                AccountKv::set(
-                    account_db,
-                    &format!("waiting_staged_commit_{}", group.group_uuid()),
-                    &serde_json::to_string(&waiting).unwrap(),
-                )
-                .await
-                .unwrap();
-
-                // finally, send the commit to the group
-                group
-                    .send_message(&api, &commit, &removed_client_uuids)
+                // if sending fails the commit stays queued, it's merged once a later flush sends it
+                if let Err(e) = group
+                    .send_commit(
+                        &mls_provider,
+                        &api,
+                        &signature,
+                        account_db,
+                        &commit,
+                        None,
+                        &exclude,
+                    )
                     .await
-                    .unwrap();
+                {
+                    warn!("unable to send commit: {}", e);
+                }
             }
             ProcessedMessageContent::ExternalJoinProposalMessage(_) => {
                 panic!("unsupported message type: {:?}", content)
@@ -319,3 +673,20 @@ impl FrontendInstance {
         Ok(())
     }
 }
+
+// stores the location of another member and records the places they entered or left with it
+async fn store_location(account_db: &DbPool, mut location: Location) -> Result<(), Error> {
+    if !RetentionSettings::load(account_db).await?.keep_raw {
+        location.raw = None;
+    }
+    location.create(account_db).await?;
+    record_place_events(account_db, &location).await?;
+    LocationSegment::delete_ending_from(
+        account_db,
+        &location.group_uuid,
+        &location.client_uuid,
+        &location.location_date,
+    )
+    .await?;
+    Ok(())
+}
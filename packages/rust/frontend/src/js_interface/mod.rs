@@ -3,14 +3,23 @@ use crate::public::init::TokioThread;
 use bridge_macro::bridge;
 use serde::Serialize;
 use sqlx::SqlitePool;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+pub mod backup;
+pub mod chat;
 pub mod client;
+pub mod device_link;
 pub mod group;
 pub mod location;
 pub mod message;
 pub mod native;
+pub mod place;
+pub mod receipt;
+pub mod retention;
+pub mod timeline;
 pub mod user;
 
 #[derive(Debug)]
@@ -37,6 +46,14 @@ pub struct FrontendInstance {
     global_database: SqlitePool,
     account_data: RwLock<Option<GlobalAccountData>>,
     device_api: DeviceApi,
+    message_stream: std::sync::Mutex<Option<JoinHandle<()>>>,
+    retention_job: std::sync::Mutex<Option<JoinHandle<()>>>,
+    // incoming messages and our own commits must be processed one at a time
+    process_lock: Mutex<()>,
+    // set while this device waits for a logged in one to link it
+    device_link: Mutex<Option<PendingDeviceLink>>,
+    // when receiving messages last added our other clients to our groups
+    last_client_sync: std::sync::Mutex<Option<Instant>>,
 }
 
 impl FrontendInstance {
@@ -50,6 +67,11 @@ impl FrontendInstance {
             global_database,
             account_data: RwLock::new(account_data),
             device_api: DeviceApi::init(),
+            message_stream: std::sync::Mutex::new(None),
+            retention_job: std::sync::Mutex::new(None),
+            process_lock: Mutex::new(()),
+            device_link: Mutex::new(None),
+            last_client_sync: std::sync::Mutex::new(None),
         }
     }
 
@@ -98,11 +120,20 @@ impl FrontendInstance {
 }
 
 use crate::application_message::Location;
-use crate::js_interface::group::Group;
+use crate::js_interface::chat::ChatMessage;
+use crate::js_interface::device_link::PendingDeviceLink;
+use crate::js_interface::group::{Group, LocationSettings};
+use crate::js_interface::location::MemberLocation;
+use crate::js_interface::message::DroppedMessage;
+use crate::js_interface::place::{Place, PlaceEvent};
+use crate::js_interface::receipt::{MessageReceipt, ReceiptSettings};
+use crate::js_interface::retention::RetentionSettings;
+use crate::js_interface::timeline::TimelineSegment;
 use crate::js_interface::user::UserOut;
 use crate::platform::DeviceApi;
 use crate::public::native_api::NativeApi;
 use crate::Error;
+use common::base64::Base64;
 
 export!(
     FrontendInstance,
@@ -116,16 +147,38 @@ export!(
     ) -> Result<(), Error>;
     login(username_or_email: String, password: String) -> Result<Uuid, Error>;
     logout() -> Result<(), Error>;
+    start_device_link(username_or_email: String, password: String) -> Result<String, Error>;
+    finish_device_link() -> Result<Option<Uuid>, Error>;
+    link_device(code: String) -> Result<bool, Error>;
     // group
     get_groups() -> Result<Vec<Group>, Error>;
     create_group() -> Result<Uuid, Error>;
     add_member(group_uuid: Uuid, user_uuid: Uuid) -> Result<(), Error>;
     remove_member(group_uuid: Uuid, user_uuid: Uuid) -> Result<(), Error>;
     leave_group(group_uuid: Uuid) -> Result<(), Error>;
-    update_group(group_uuid: Uuid, name: Option<String>) -> Result<(), Error>;
-    send_group_status(group_uuid: Uuid) -> Result<(), Error>;
+    update_group(group_uuid: Uuid, name: Option<String>) -> Result<Uuid, Error>;
+    send_group_status(group_uuid: Uuid) -> Result<Uuid, Error>;
+    set_group_image(group_uuid: Uuid, image: Option<Base64>) -> Result<Uuid, Error>;
+    get_location_settings(group_uuid: Uuid) -> Result<LocationSettings, Error>;
+    set_location_settings(group_uuid: Uuid, settings: LocationSettings) -> Result<(), Error>;
     // message
     receive_messages() -> Result<usize, Error>;
+    subscribe_to_messages() -> Result<(), Error>;
+    unsubscribe_from_messages() -> Result<(), Error>;
+    flush_outbox() -> Result<(), Error>;
+    get_dropped_messages() -> Result<Vec<DroppedMessage>, Error>;
+    dismiss_dropped_message(id: i32) -> Result<(), Error>;
+    // chat
+    send_text(group_uuid: Uuid, body: String, reply_to: Option<Uuid>) -> Result<Uuid, Error>;
+    get_messages(
+        group_uuid: Uuid,
+        before_timestamp: i64,
+        amount: u32
+    ) -> Result<Vec<ChatMessage>, Error>;
+    mark_messages_read(group_uuid: Uuid, message_uuids: Vec<Uuid>) -> Result<(), Error>;
+    get_receipts(group_uuid: Uuid, message_uuid: Uuid) -> Result<Vec<MessageReceipt>, Error>;
+    get_receipt_settings() -> Result<ReceiptSettings, Error>;
+    set_receipt_settings(settings: ReceiptSettings) -> Result<(), Error>;
     // location
     get_location(
         group_uuid: Uuid,
@@ -139,14 +192,55 @@ export!(
         from_timestamp: i64,
         to_timestamp: i64
     ) -> Result<i64, ()>;
-    send_location(
+    get_latest_locations(group_uuid: Uuid) -> Result<Vec<MemberLocation>, Error>;
+    export_locations(
         group_uuid: Uuid,
-        longitude: f64,
+        client: Uuid,
+        from_timestamp: i64,
+        to_timestamp: i64,
+        format: String
+    ) -> Result<String, Error>;
+    get_timeline(
+        group_uuid: Uuid,
+        client: Uuid,
+        from_timestamp: i64,
+        to_timestamp: i64
+    ) -> Result<Vec<TimelineSegment>, Error>;
+    send_location(group_uuid: Uuid, location: Location) -> Result<Option<Uuid>, ()>;
+    send_locations(group_uuid: Uuid, locations: Vec<Location>) -> Result<Option<Uuid>, ()>;
+    request_member_location(group_uuid: Uuid, user_uuid: Option<Uuid>) -> Result<(), Error>;
+    // places
+    create_place(
+        group_uuid: Uuid,
+        name: String,
         latitude: f64,
-        timestamp: i64
-    ) -> Result<(), ()>;
+        longitude: f64,
+        radius: f64
+    ) -> Result<Uuid, Error>;
+    get_places(group_uuid: Uuid) -> Result<Vec<Place>, Error>;
+    delete_place(place_uuid: Uuid) -> Result<(), Error>;
+    get_place_events(
+        group_uuid: Uuid,
+        before_timestamp: i64,
+        amount: u32
+    ) -> Result<Vec<PlaceEvent>, Error>;
+    get_retention_settings() -> Result<RetentionSettings, Error>;
+    set_retention_settings(settings: RetentionSettings) -> Result<(), Error>;
+    compact_locations() -> Result<(), Error>;
     // clients
     replace_key_packages() -> Result<(), Error>;
+    register_push_token(token: String) -> Result<(), Error>;
+    revoke_push_token() -> Result<(), Error>;
+    sync_clients() -> Result<usize, Error>;
+    // backup
+    export_backup(passphrase: String) -> Result<Base64, Error>;
+    restore_backup(
+        backup: Base64,
+        passphrase: String,
+        username_or_email: String,
+        password: String
+    ) -> Result<Uuid, Error>;
+    request_rejoin() -> Result<usize, Error>;
     search(query: String) -> Result<Vec<UserOut>, Error>;
     // native
     request_location_permissions() -> Result<bool, ()>;
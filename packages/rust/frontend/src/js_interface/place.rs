@@ -0,0 +1,127 @@
+use crate::js_interface::FrontendInstance;
+use crate::models::account::group::Group as GroupModel;
+use crate::models::account::place::Place as PlaceModel;
+use crate::models::account::place_event::PlaceEvent as PlaceEventModel;
+use crate::Error;
+use bridge_macro::bridge;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Place {
+    pub uuid: Uuid,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    // metres
+    pub radius: f64,
+}
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlaceEvent {
+    pub place_uuid: Uuid,
+    pub client_uuid: Uuid,
+    // false if the client left the place
+    pub entered: bool,
+    pub timestamp: i64,
+}
+
+// comparisons with NaN are false, so it's rejected too
+fn check_place(latitude: f64, longitude: f64, radius: f64) -> Result<(), Error> {
+    if !(radius > 0.0 && radius.is_finite()) {
+        return Err(Error::InvalidPlaceRadius);
+    }
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(Error::InvalidCoordinates);
+    }
+    Ok(())
+}
+
+impl FrontendInstance {
+    // places are only stored on this client, they are never sent to the group or the server
+    #[bridge]
+    pub async fn create_place(
+        &self,
+        group_uuid: Uuid,
+        name: String,
+        latitude: f64,
+        longitude: f64,
+        radius: f64,
+    ) -> Result<Uuid, Error> {
+        check_place(latitude, longitude, radius)?;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        GroupModel::from_uuid(&global_data.database, group_uuid)
+            .await?
+            .filter(|group| group.in_group)
+            .ok_or_else(|| Error::GroupNotFound)?;
+        let uuid = Uuid::new_v4();
+
+        PlaceModel {
+            id: 0,
+            uuid,
+            group_uuid,
+            name,
+            latitude,
+            longitude,
+            radius,
+        }
+        .create(&global_data.database)
+        .await?;
+
+        Ok(uuid)
+    }
+
+    #[bridge]
+    pub async fn get_places(&self, group_uuid: Uuid) -> Result<Vec<Place>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let places = PlaceModel::from_group_uuid(&global_data.database, &group_uuid).await?;
+
+        Ok(places
+            .into_iter()
+            .map(|place| Place {
+                uuid: place.uuid,
+                name: place.name,
+                latitude: place.latitude,
+                longitude: place.longitude,
+                radius: place.radius,
+            })
+            .collect())
+    }
+
+    #[bridge]
+    pub async fn delete_place(&self, place_uuid: Uuid) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        PlaceModel::delete_by_uuid(&global_data.database, &place_uuid).await?;
+        Ok(())
+    }
+
+    #[bridge]
+    pub async fn get_place_events(
+        &self,
+        group_uuid: Uuid,
+        before_timestamp: i64,
+        amount: u32,
+    ) -> Result<Vec<PlaceEvent>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let before = NaiveDateTime::from_timestamp_millis(before_timestamp).unwrap();
+        let events =
+            PlaceEventModel::query(&global_data.database, &group_uuid, &before, amount).await?;
+
+        Ok(events
+            .into_iter()
+            .map(|event| PlaceEvent {
+                place_uuid: event.place_uuid,
+                client_uuid: event.client_uuid,
+                entered: event.entered,
+                timestamp: event.event_date.timestamp_millis(),
+            })
+            .collect())
+    }
+}
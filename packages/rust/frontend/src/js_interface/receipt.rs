@@ -0,0 +1,196 @@
+use crate::api::BubbleApi;
+use crate::application_message::{Message, Receipt};
+use crate::helper::bubble_group::BubbleGroup;
+use crate::helper::helper::get_this_client_mls_resources;
+use crate::js_interface::FrontendInstance;
+use crate::mls_provider::MlsProvider;
+use crate::models::account::chat_message::ChatMessage as ChatMessageModel;
+use crate::models::account::receipt::Receipt as ReceiptModel;
+use crate::models::kv::AccountKv;
+use crate::types::DbPool;
+use crate::Error;
+use bridge_macro::bridge;
+use openmls_basic_credential::SignatureKeyPair;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::Utc;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const RECEIPT_KEY: &str = "receipts";
+
+// which receipts we send, the receipts of other members are always stored
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReceiptSettings {
+    pub send_delivery_receipts: bool,
+    pub send_read_receipts: bool,
+}
+
+impl Default for ReceiptSettings {
+    fn default() -> Self {
+        Self {
+            send_delivery_receipts: true,
+            send_read_receipts: true,
+        }
+    }
+}
+
+impl ReceiptSettings {
+    pub async fn load(db: &DbPool) -> Result<Self, Error> {
+        Ok(match AccountKv::get(db, RECEIPT_KEY).await? {
+            Some(settings) => serde_json::from_str(&settings)?,
+            None => Self::default(),
+        })
+    }
+
+    async fn save(&self, db: &DbPool) -> Result<(), Error> {
+        AccountKv::set(db, RECEIPT_KEY, &serde_json::to_string(self)?).await?;
+        Ok(())
+    }
+}
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MessageReceipt {
+    pub user_uuid: Uuid,
+    pub client_uuid: Uuid,
+    // null if the client didn't receive the message yet
+    pub delivered_timestamp: Option<i64>,
+    pub read_timestamp: Option<i64>,
+}
+
+// sends a single receipt for the messages, groups we're no longer in are skipped
+#[allow(clippy::too_many_arguments)]
+pub async fn send_receipt(
+    mls_provider: &MlsProvider,
+    api: &BubbleApi,
+    signer: &SignatureKeyPair,
+    db: &DbPool,
+    client_uuid: &Uuid,
+    group_uuid: &Uuid,
+    ids: Vec<Uuid>,
+    read: bool,
+) -> Result<(), Error> {
+    let mut group =
+        BubbleGroup::new_from_uuid(group_uuid, mls_provider).ok_or(Error::MLSGroupLoad)?;
+    if !group.is_active() {
+        return Ok(());
+    }
+    let message = Message::Receipt(Receipt {
+        ids,
+        read,
+        timestamp: Utc::now().timestamp_millis(),
+    });
+    group
+        .send_application_message(mls_provider, api, signer, db, &message, &[*client_uuid])
+        .await?;
+    group.save_if_needed(mls_provider)?;
+    Ok(())
+}
+
+impl FrontendInstance {
+    #[bridge]
+    pub async fn get_receipt_settings(&self) -> Result<ReceiptSettings, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        ReceiptSettings::load(&global_data.database).await
+    }
+
+    #[bridge]
+    pub async fn set_receipt_settings(&self, settings: ReceiptSettings) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        settings.save(&global_data.database).await
+    }
+
+    // tells the group we read the messages, nothing is sent if read receipts are off
+    #[bridge]
+    pub async fn mark_messages_read(
+        &self,
+        group_uuid: Uuid,
+        message_uuids: Vec<Uuid>,
+    ) -> Result<(), Error> {
+        if message_uuids.is_empty() {
+            return Ok(());
+        }
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        if !ReceiptSettings::load(account_db).await?.send_read_receipts {
+            return Ok(());
+        }
+        let client_uuid = global_data.client_uuid.read().await.unwrap();
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let api = BubbleApi::new(
+            global_data.domain.clone(),
+            Some(global_data.bearer.read().await.clone()),
+        );
+        let (signature, _) = get_this_client_mls_resources(
+            &global_data.user_uuid,
+            &client_uuid,
+            account_db,
+            &mls_provider,
+        )
+        .await?;
+
+        send_receipt(
+            &mls_provider,
+            &api,
+            &signature,
+            account_db,
+            &client_uuid,
+            &group_uuid,
+            message_uuids,
+            true,
+        )
+        .await
+    }
+
+    // the delivered and read state of the message for every other member's client, the message is a text or one of
+    // the locations or statuses we sent
+    #[bridge]
+    pub async fn get_receipts(
+        &self,
+        group_uuid: Uuid,
+        message_uuid: Uuid,
+    ) -> Result<Vec<MessageReceipt>, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let client_uuid = global_data.client_uuid.read().await.unwrap();
+        let mls_provider = MlsProvider::new(account_db.clone());
+        let group = BubbleGroup::new_from_uuid(&group_uuid, &mls_provider)
+            .ok_or_else(|| Error::MLSGroupLoad)?;
+        // only texts are stored by their id, any other id is one of the locations or statuses we sent
+        let sender = ChatMessageModel::from_uuid(account_db, &group_uuid, &message_uuid)
+            .await?
+            .map_or(client_uuid, |message| message.client_uuid);
+        let mut receipts: HashMap<_, _> =
+            ReceiptModel::from_message_uuid(account_db, &group_uuid, &message_uuid)
+                .await?
+                .into_iter()
+                .map(|receipt| (receipt.client_uuid, receipt))
+                .collect();
+
+        // we don't receive our own receipts
+        Ok(group
+            .get_group_members()?
+            .into_iter()
+            .filter(|member| member.client_uuid != sender && member.client_uuid != client_uuid)
+            .map(|member| {
+                let receipt = receipts.remove(&member.client_uuid);
+                MessageReceipt {
+                    user_uuid: member.user_uuid,
+                    client_uuid: member.client_uuid,
+                    delivered_timestamp: receipt
+                        .as_ref()
+                        .map(|receipt| receipt.delivered_date.timestamp_millis()),
+                    read_timestamp: receipt
+                        .and_then(|receipt| receipt.read_date)
+                        .map(|date| date.timestamp_millis()),
+                }
+            })
+            .collect())
+    }
+}
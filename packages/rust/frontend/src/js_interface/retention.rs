@@ -0,0 +1,118 @@
+use crate::js_interface::FrontendInstance;
+use crate::models::account::location::Location as LocationModel;
+use crate::models::account::location_segment::LocationSegment;
+use crate::models::kv::AccountKv;
+use crate::types::DbPool;
+use crate::Error;
+use bridge_macro::bridge;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+
+const RETENTION_KEY: &str = "location_retention";
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+// how long the locations of other members are kept
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RetentionSettings {
+    // keep the message each location was received in
+    pub keep_raw: bool,
+    // locations older than this are reduced to one per `downsample_interval_minutes`, never if null
+    pub downsample_after_days: Option<u32>,
+    pub downsample_interval_minutes: u32,
+    // locations older than this are deleted, never if null
+    pub delete_after_days: Option<u32>,
+}
+
+// locations are kept as they are until the user opts in to downsampling or deleting them
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            keep_raw: false,
+            downsample_after_days: None,
+            downsample_interval_minutes: 15,
+            delete_after_days: None,
+        }
+    }
+}
+
+impl RetentionSettings {
+    pub async fn load(db: &DbPool) -> Result<Self, Error> {
+        Ok(match AccountKv::get(db, RETENTION_KEY).await? {
+            Some(settings) => serde_json::from_str(&settings)?,
+            None => Self::default(),
+        })
+    }
+
+    async fn save(&self, db: &DbPool) -> Result<(), Error> {
+        AccountKv::set(db, RETENTION_KEY, &serde_json::to_string(self)?).await?;
+        Ok(())
+    }
+}
+
+fn days_ago(days: u32) -> NaiveDateTime {
+    NaiveDateTime::from_timestamp_millis(Utc::now().timestamp_millis() - days as i64 * DAY).unwrap()
+}
+
+impl FrontendInstance {
+    #[bridge]
+    pub async fn get_retention_settings(&self) -> Result<RetentionSettings, Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        RetentionSettings::load(&global_data.database).await
+    }
+
+    #[bridge]
+    pub async fn set_retention_settings(&self, settings: RetentionSettings) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        settings.save(&global_data.database).await
+    }
+
+    // applies the retention settings to the stored locations, this also runs every RETENTION_INTERVAL
+    #[bridge]
+    pub async fn compact_locations(&self) -> Result<(), Error> {
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let settings = RetentionSettings::load(account_db).await?;
+
+        if !settings.keep_raw {
+            LocationModel::clear_raw(account_db).await?;
+        }
+        if let Some(days) = settings.delete_after_days {
+            LocationModel::delete_before(account_db, &days_ago(days)).await?;
+            LocationSegment::delete_before(account_db, &days_ago(days)).await?;
+        }
+        // the segments of downsampled locations are kept, they were computed from all of them
+        if let Some(days) = settings.downsample_after_days {
+            let interval =
+                Duration::from_secs(settings.downsample_interval_minutes.max(1) as u64 * 60);
+            LocationModel::downsample(account_db, &days_ago(days), interval).await?;
+        }
+        Ok(())
+    }
+
+    // spawns the job that periodically compacts the locations on the instance's tokio thread
+    pub fn start_location_retention(self: &Arc<Self>) {
+        let mut retention_job = self.retention_job.lock().unwrap();
+        if retention_job.is_some() {
+            return;
+        }
+        let instance = self.clone();
+        *retention_job = Some(self.static_data.tokio.handle.spawn(async move {
+            loop {
+                if instance.logged_in().await {
+                    if let Err(e) = instance.compact_locations().await {
+                        warn!("location retention error: {}", e);
+                    }
+                }
+                tokio::time::sleep(RETENTION_INTERVAL).await;
+            }
+        }));
+    }
+}
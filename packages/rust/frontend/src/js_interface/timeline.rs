@@ -0,0 +1,65 @@
+use crate::helper::timeline::update_segments;
+use crate::js_interface::FrontendInstance;
+use crate::models::account::location_segment::LocationSegment;
+use crate::Error;
+use bridge_macro::bridge;
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[bridge]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TimelineSegment {
+    // false if this is a trip
+    pub stay: bool,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    // milliseconds
+    pub duration: i64,
+    // the centre of a stay, null for trips
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    // metres travelled, 0 for stays
+    pub distance: f64,
+}
+
+impl From<LocationSegment> for TimelineSegment {
+    fn from(segment: LocationSegment) -> Self {
+        let start_timestamp = segment.start_date.timestamp_millis();
+        let end_timestamp = segment.end_date.timestamp_millis();
+        Self {
+            stay: segment.stay,
+            start_timestamp,
+            end_timestamp,
+            duration: end_timestamp - start_timestamp,
+            latitude: segment.latitude,
+            longitude: segment.longitude,
+            distance: segment.distance,
+        }
+    }
+}
+
+impl FrontendInstance {
+    // the stays and trips of the client overlapping the range, oldest first
+    #[bridge]
+    pub async fn get_timeline(
+        &self,
+        group_uuid: Uuid,
+        client: Uuid,
+        from_timestamp: i64,
+        to_timestamp: i64,
+    ) -> Result<Vec<TimelineSegment>, Error> {
+        // locations can't be stored while the segments are updated
+        let _processing = self.process_lock.lock().await;
+        let global = self.account_data.read().await;
+        let global_data = global.as_ref().ok_or_else(|| Error::NoGlobalAccountData)?;
+        let account_db = &global_data.database;
+        let from = NaiveDateTime::from_timestamp_millis(from_timestamp).unwrap();
+        let to = NaiveDateTime::from_timestamp_millis(to_timestamp).unwrap();
+
+        update_segments(account_db, &group_uuid, &client).await?;
+        let segments = LocationSegment::query(account_db, &group_uuid, &client, &from, &to).await?;
+
+        Ok(segments.into_iter().map(TimelineSegment::from).collect())
+    }
+}
@@ -57,10 +57,20 @@ impl FrontendInstance {
         let user_uuid = api
             .register(email, username, password, name, public.clone())
             .await?;
+        self.create_account_database(&user_uuid, &user_keys.secret)
+            .await
+    }
+
+    // the account database holds the user's identity key, login_with_token creates a client with it
+    pub(crate) async fn create_account_database(
+        &self,
+        user_uuid: &Uuid,
+        user_private_key: &SecretKey,
+    ) -> Result<(), Error> {
         fs::create_dir_all(format!("{}/accounts", &self.static_data.data_directory)).unwrap();
         let path = format!(
             "{}/accounts/{}.db",
-            &self.static_data.data_directory, &user_uuid
+            &self.static_data.data_directory, user_uuid
         );
         let account_db = SqlitePoolOptions::new()
             .connect(&format!("sqlite:{}?mode=rwc", path))
@@ -71,7 +81,7 @@ impl FrontendInstance {
         AccountKv::set(
             &account_db,
             "user_private_key",
-            &base64::serialize(user_keys.secret.to_bytes().as_ref()),
+            &base64::serialize(user_private_key.to_bytes().as_ref()),
         )
         .await?;
         AccountKv::set(&account_db, "domain", &self.static_data.domain).await?;
@@ -86,7 +96,7 @@ impl FrontendInstance {
         Ok(user_uuid)
     }
 
-    async fn login_with_token(&self, res: SessionTokenResponse) -> Result<Uuid, Error> {
+    pub(crate) async fn login_with_token(&self, res: SessionTokenResponse) -> Result<Uuid, Error> {
         let path = format!(
             "{}/accounts/{}.db",
             &self.static_data.data_directory, &res.user_uuid
@@ -183,6 +193,7 @@ impl FrontendInstance {
     #[bridge]
     pub async fn logout(&self) -> Result<(), Error> {
         GlobalKv::delete(&self.global_database, "current_account").await?;
+        self.unsubscribe_from_messages().await?;
         self.account_data.write().await.take();
         Ok(())
     }
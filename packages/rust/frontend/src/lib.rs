@@ -13,7 +13,10 @@ use crate::helper::resource_fetcher::ResourceError;
 use crate::js_interface::FrontendInstance;
 use crate::virtual_memory::VirtualMemory;
 use once_cell::sync::Lazy;
-use openmls::prelude::{AddMembersError, LeaveGroupError, RemoveMembersError};
+use openmls::prelude::{
+    AddMembersError, LeaveGroupError, MergeCommitError, MergePendingCommitError,
+    ProcessMessageError, RemoveMembersError,
+};
 use serde::{Serialize, Serializer};
 use serde_json::json;
 use sqlx::migrate::MigrateError;
@@ -72,10 +75,56 @@ pub enum Error {
     UnexpectedWelcome,
     #[error("could not read client_uuid")]
     ReadClientUUID,
-    #[error("logging into an account on a device other than the one it was created on is not supported yet")]
+    #[error("this device isn't linked to the account yet, link it from a logged in device")]
     WrongDevice,
     #[error("no clients found for the requested user")]
     NoClientsFound,
+    #[error("another commit was accepted for this epoch")]
+    CommitConflict(Vec<u8>),
+    #[error("our commit was rejected too many times")]
+    CommitRejected,
+    #[error("the queued commit no longer matches the group's state")]
+    StaleCommit,
+    #[error("group not found")]
+    GroupNotFound,
+    #[error("unknown export format: {0}")]
+    UnknownExportFormat(String),
+    #[error("crypto error: {0}")]
+    Crypto(#[from] openmls_traits::types::CryptoError),
+    #[error("images have to be PNG, JPEG or WebP")]
+    InvalidImageFormat,
+    #[error("image is larger than {0} bytes")]
+    ImageTooLarge(usize),
+    #[error("a place's radius has to be a positive number of metres")]
+    InvalidPlaceRadius,
+    #[error("latitude has to be between -90 and 90, longitude between -180 and 180")]
+    InvalidCoordinates,
+    #[error("a location grid has to be at least one metre")]
+    InvalidLocationPrecision,
+    #[error("location sharing can only be paused until a time in the future")]
+    InvalidPausedUntil,
+    #[error("messages can't be empty")]
+    EmptyMessage,
+    #[error("no device link was started")]
+    NoDeviceLink,
+    #[error("the device link's key doesn't match its code")]
+    DeviceLinkKeyMismatch,
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("the backup passphrase can't be empty")]
+    EmptyPassphrase,
+    #[error("the backup is damaged or the passphrase is wrong")]
+    InvalidBackup,
+    #[error("log out before restoring a backup")]
+    LoggedIn,
+    #[error("expected a commit")]
+    ExpectedCommit,
+    #[error("process message error: {0}")]
+    ProcessMessage(#[from] ProcessMessageError),
+    #[error("merge commit error: {0}")]
+    MergeCommit(#[from] MergeCommitError<mls_provider::keystore::SqlxError>),
+    #[error("merge pending commit error: {0}")]
+    MergePendingCommit(#[from] MergePendingCommitError<mls_provider::keystore::SqlxError>),
 
     #[error("don't know what to return for this error yet")]
     TestingError,
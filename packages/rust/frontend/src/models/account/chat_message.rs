@@ -0,0 +1,94 @@
+use crate::application_message::Text;
+use crate::types::DbPool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use uuid::Uuid;
+
+// a text message sent in a group, ours included
+pub struct ChatMessage {
+    pub id: i32,
+    // generated by the sending client, identifies the message across clients
+    pub uuid: Uuid,
+    pub group_uuid: Uuid,
+    pub client_uuid: Uuid,
+    pub body: String,
+    pub reply_to: Option<Uuid>,
+    // when the sender wrote the message
+    pub message_date: NaiveDateTime,
+}
+
+impl From<&SqliteRow> for ChatMessage {
+    fn from(row: &SqliteRow) -> Self {
+        let message_date: i64 = row.get("message_date");
+        let message_date = NaiveDateTime::from_timestamp_millis(message_date).unwrap();
+        Self {
+            id: row.get("id"),
+            uuid: row.get("uuid"),
+            group_uuid: row.get("group_uuid"),
+            client_uuid: row.get("client_uuid"),
+            body: row.get("body"),
+            reply_to: row.get("reply_to"),
+            message_date,
+        }
+    }
+}
+
+impl ChatMessage {
+    pub fn new(client_uuid: Uuid, group_uuid: Uuid, text: &Text) -> Self {
+        Self {
+            id: 0,
+            uuid: text.id,
+            group_uuid,
+            client_uuid,
+            body: text.body.clone(),
+            reply_to: text.reply_to,
+            message_date: NaiveDateTime::from_timestamp_millis(text.timestamp).unwrap(),
+        }
+    }
+
+    // a message we already have is ignored, e.g. when it's delivered twice
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        self.id = sqlx::query("INSERT OR IGNORE INTO chat_message (uuid, group_uuid, client_uuid, body, reply_to, message_date) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(self.uuid)
+            .bind(self.group_uuid)
+            .bind(self.client_uuid)
+            .bind(&self.body)
+            .bind(self.reply_to)
+            .bind(self.message_date.timestamp_millis())
+            .execute(db)
+            .await?
+            .last_insert_rowid() as i32;
+        Ok(())
+    }
+
+    pub async fn from_uuid(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        uuid: &Uuid,
+    ) -> Result<Option<ChatMessage>, sqlx::Error> {
+        let message = sqlx::query("SELECT * FROM chat_message WHERE group_uuid = $1 AND uuid = $2")
+            .bind(group_uuid)
+            .bind(uuid)
+            .fetch_optional(db)
+            .await?
+            .as_ref()
+            .map(ChatMessage::from);
+        Ok(message)
+    }
+
+    pub async fn query(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        before: &NaiveDateTime,
+        amount: u32,
+    ) -> Result<Vec<ChatMessage>, sqlx::Error> {
+        let messages = sqlx::query("SELECT * FROM chat_message WHERE message_date < $1 AND group_uuid = $2 ORDER BY message_date DESC LIMIT $3")
+            .bind(before.timestamp_millis())
+            .bind(group_uuid)
+            .bind(amount)
+            .fetch_all(db)
+            .await?;
+        Ok(messages.iter().map(ChatMessage::from).collect())
+    }
+}
@@ -1,4 +1,6 @@
+use crate::application_message::{Attachment, LocationPrecision};
 use crate::types::DbPool;
+use common::base64::Base64;
 use sqlx::sqlite::SqliteRow;
 use sqlx::types::chrono::NaiveDateTime;
 use sqlx::Row;
@@ -10,8 +12,21 @@ pub struct Group {
     pub uuid: Uuid,
     pub name: Option<String>,
     pub image: Option<Vec<u8>>,
+    // the image is sent with this attachment again while it's uploaded for the same clients
+    pub image_attachment: Option<Attachment>,
+    // None if another member uploaded it
+    pub image_attachment_clients: Option<Vec<Uuid>>,
     pub updated_at: NaiveDateTime,
     pub in_group: bool,
+    // messages for this group expired on the server before we fetched them, so we have to rejoin
+    pub out_of_sync: bool,
+    // whether we send our location to this group
+    pub location_sharing: bool,
+    pub location_paused_until: Option<NaiveDateTime>,
+    // how much our locations are coarsened before they are sent
+    pub location_precision: LocationPrecision,
+    // when we last replied to a location request, they're rate limited per group
+    pub location_request_answered_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
 }
 
@@ -22,8 +37,26 @@ impl From<&SqliteRow> for Group {
             uuid: row.get("uuid"),
             name: row.get("name"),
             image: row.get("image"),
+            image_attachment: row
+                .get::<Option<Vec<u8>>, _>("image_attachment_hash")
+                .map(|hash| Attachment {
+                    hash: Base64(hash),
+                    key: Base64(row.get("image_attachment_key")),
+                    nonce: Base64(row.get("image_attachment_nonce")),
+                }),
+            image_attachment_clients: row
+                .get::<Option<&str>, _>("image_attachment_clients")
+                .map(|clients| serde_json::from_str(clients).unwrap()),
             updated_at: row.get("updated_at"),
             in_group: row.get("in_group"),
+            out_of_sync: row.get("out_of_sync"),
+            location_sharing: row.get("location_sharing"),
+            location_paused_until: row.get("location_paused_until"),
+            location_precision: LocationPrecision::from_columns(
+                row.get("location_precision_mode"),
+                row.get("location_precision"),
+            ),
+            location_request_answered_at: row.get("location_request_answered_at"),
             created_at: row.get("created_at"),
         }
     }
@@ -32,12 +65,25 @@ impl From<&SqliteRow> for Group {
 impl Group {
     pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
         *self = (&sqlx::query(
-            "INSERT INTO \"group\" (uuid, name, image, in_group, updated_at) VALUES ($1, $2, $3, $4, $5) RETURNING *;",
+            "INSERT INTO \"group\" (uuid, name, image, image_attachment_hash, image_attachment_key, image_attachment_nonce, image_attachment_clients, in_group, out_of_sync, location_sharing, location_paused_until, location_precision_mode, location_precision, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) RETURNING *;",
         )
             .bind(self.uuid)
             .bind(&self.name)
             .bind(&self.image)
+            .bind(self.image_attachment.as_ref().map(|a| &a.hash.0))
+            .bind(self.image_attachment.as_ref().map(|a| &a.key.0))
+            .bind(self.image_attachment.as_ref().map(|a| &a.nonce.0))
+            .bind(
+                self.image_attachment_clients
+                    .as_ref()
+                    .map(|clients| serde_json::to_string(clients).unwrap()),
+            )
             .bind(self.in_group)
+            .bind(self.out_of_sync)
+            .bind(self.location_sharing)
+            .bind(self.location_paused_until)
+            .bind(self.location_precision.mode())
+            .bind(self.location_precision.grid_metres())
             .bind(self.updated_at)
             .fetch_one(db)
             .await?)
@@ -46,17 +92,31 @@ impl Group {
     }
 
     pub async fn update(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
-        *self = (&sqlx::query(
-            "UPDATE \"group\" SET name = $1, image = $2, updated_at = $3, in_group = $4 WHERE id = $5 RETURNING *;",
+        // no RETURNING, it leaves the update uncommitted until the statement is reset and the next read might not see it
+        sqlx::query(
+            "UPDATE \"group\" SET name = $1, image = $2, image_attachment_hash = $3, image_attachment_key = $4, image_attachment_nonce = $5, image_attachment_clients = $6, updated_at = $7, in_group = $8, out_of_sync = $9, location_sharing = $10, location_paused_until = $11, location_precision_mode = $12, location_precision = $13, location_request_answered_at = $14 WHERE id = $15;",
         )
             .bind(&self.name)
             .bind(&self.image)
+            .bind(self.image_attachment.as_ref().map(|a| &a.hash.0))
+            .bind(self.image_attachment.as_ref().map(|a| &a.key.0))
+            .bind(self.image_attachment.as_ref().map(|a| &a.nonce.0))
+            .bind(
+                self.image_attachment_clients
+                    .as_ref()
+                    .map(|clients| serde_json::to_string(clients).unwrap()),
+            )
             .bind(self.updated_at)
             .bind(self.in_group)
+            .bind(self.out_of_sync)
+            .bind(self.location_sharing)
+            .bind(self.location_paused_until)
+            .bind(self.location_precision.mode())
+            .bind(self.location_precision.grid_metres())
+            .bind(self.location_request_answered_at)
             .bind(self.id)
-            .fetch_one(db)
-            .await?)
-            .into();
+            .execute(db)
+            .await?;
         Ok(())
     }
 
@@ -72,6 +132,26 @@ impl Group {
         Ok(locations.iter().map(Group::from).collect())
     }
 
+    // groups we currently send our location to
+    pub async fn all_sharing_location(
+        db: &DbPool,
+        now: NaiveDateTime,
+    ) -> Result<Vec<Group>, sqlx::Error> {
+        let groups = sqlx::query("SELECT * FROM \"group\" WHERE in_group = TRUE AND location_sharing = TRUE AND (location_paused_until IS NULL OR location_paused_until <= $1)")
+            .bind(now)
+            .fetch_all(db)
+            .await?;
+        Ok(groups.iter().map(Group::from).collect())
+    }
+
+    pub fn shares_location(&self, now: NaiveDateTime) -> bool {
+        self.in_group
+            && self.location_sharing
+            && self
+                .location_paused_until
+                .is_none_or(|paused_until| paused_until <= now)
+    }
+
     pub async fn from_uuid(db: &DbPool, uuid: Uuid) -> Result<Option<Group>, sqlx::Error> {
         let row = sqlx::query("SELECT * FROM \"group\" WHERE uuid = $1")
             .bind(uuid)
@@ -1,10 +1,12 @@
 use crate::types::DbPool;
+use common::http_types::DeliveredMessage;
 use sqlx::sqlite::SqliteRow;
-use sqlx::types::chrono::NaiveDateTime;
+use sqlx::types::chrono::{NaiveDateTime, Utc};
 use sqlx::Row;
 
 pub struct Inbox {
     pub id: i32,
+    pub server_id: i32,
     pub message: Vec<u8>,
     pub server_received_date: NaiveDateTime,
     pub received_date: NaiveDateTime,
@@ -14,6 +16,7 @@ impl From<&SqliteRow> for Inbox {
     fn from(row: &SqliteRow) -> Self {
         Self {
             id: row.get("id"),
+            server_id: row.get("server_id"),
             message: row.get("message"),
             server_received_date: row.get("server_received_date"),
             received_date: row.get("received_date"),
@@ -21,16 +24,32 @@ impl From<&SqliteRow> for Inbox {
     }
 }
 
+impl From<DeliveredMessage> for Inbox {
+    fn from(message: DeliveredMessage) -> Self {
+        Self {
+            id: 0,
+            server_id: message.id,
+            message: message.message.0,
+            server_received_date: NaiveDateTime::from_timestamp_millis(message.received_date)
+                .unwrap(),
+            received_date: Utc::now().naive_utc(),
+        }
+    }
+}
+
 impl Inbox {
+    // a message that is already in the inbox (because it was delivered again before being acknowledged) is ignored
     pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
-        *self =
-            (&sqlx::query("INSERT INTO inbox (message, server_received_date, received_date) VALUES (?, ?, ?) RETURNING *")
-                .bind(&self.message)
-                .bind(self.server_received_date)
-                .bind(self.received_date)
-                .fetch_one(db)
-                .await?)
-                .into();
+        let row = sqlx::query("INSERT OR IGNORE INTO inbox (server_id, message, server_received_date, received_date) VALUES (?, ?, ?, ?) RETURNING *")
+            .bind(self.server_id)
+            .bind(&self.message)
+            .bind(self.server_received_date)
+            .bind(self.received_date)
+            .fetch_optional(db)
+            .await?;
+        if let Some(row) = row {
+            *self = (&row).into();
+        }
         Ok(())
     }
 
@@ -48,4 +67,9 @@ impl Inbox {
             .await?;
         Ok(())
     }
+
+    pub async fn delete_all(db: &DbPool) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM inbox").execute(db).await?;
+        Ok(())
+    }
 }
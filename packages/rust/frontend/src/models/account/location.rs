@@ -1,9 +1,13 @@
+use crate::application_message::Location as LocationMessage;
+use crate::application_message::LocationPrecision;
+use crate::models::get_real;
 use crate::types::DbPool;
 use crate::Error;
 
 use sqlx::sqlite::SqliteRow;
 use sqlx::types::chrono::NaiveDateTime;
 use sqlx::{Row, SqlitePool};
+use std::time::Duration;
 
 use uuid::Uuid;
 
@@ -14,7 +18,17 @@ pub struct Location {
     pub longitude: f64,
     pub latitude: f64,
     pub location_date: NaiveDateTime,
-    pub raw: Vec<u8>,
+    pub precision: LocationPrecision,
+    pub altitude: Option<f64>,
+    pub floor: Option<i32>,
+    pub course: Option<f64>,
+    pub speed: Option<f64>,
+    pub horizontal_accuracy: Option<f64>,
+    pub vertical_accuracy: Option<f64>,
+    pub course_accuracy: Option<f64>,
+    pub speed_accuracy: Option<f64>,
+    // the message the location was received in, only kept if the retention settings say so
+    pub raw: Option<Vec<u8>>,
     pub created_date: NaiveDateTime,
 }
 
@@ -26,9 +40,21 @@ impl From<&SqliteRow> for Location {
             id: row.get("id"),
             client_uuid: row.get("client_uuid"),
             group_uuid: row.get("group_uuid"),
-            longitude: row.get("longitude"),
-            latitude: row.get("latitude"),
+            longitude: get_real(row, "longitude"),
+            latitude: get_real(row, "latitude"),
             location_date,
+            precision: LocationPrecision::from_columns(
+                row.get("precision_mode"),
+                row.get("precision"),
+            ),
+            altitude: get_real(row, "altitude"),
+            floor: row.get("floor"),
+            course: get_real(row, "course"),
+            speed: get_real(row, "speed"),
+            horizontal_accuracy: get_real(row, "horizontal_accuracy"),
+            vertical_accuracy: get_real(row, "vertical_accuracy"),
+            course_accuracy: get_real(row, "course_accuracy"),
+            speed_accuracy: get_real(row, "speed_accuracy"),
             raw: row.get("raw"),
             created_date: row.get("created_date"),
         }
@@ -36,13 +62,50 @@ impl From<&SqliteRow> for Location {
 }
 
 impl Location {
+    pub fn new(
+        client_uuid: Uuid,
+        group_uuid: Uuid,
+        location: LocationMessage,
+        raw: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            id: 0,
+            client_uuid,
+            group_uuid,
+            longitude: location.longitude,
+            latitude: location.latitude,
+            location_date: NaiveDateTime::from_timestamp_millis(location.timestamp).unwrap(),
+            precision: location.precision,
+            altitude: location.altitude,
+            floor: location.floor,
+            course: location.course,
+            speed: location.speed,
+            horizontal_accuracy: location.horizontal_accuracy,
+            vertical_accuracy: location.vertical_accuracy,
+            course_accuracy: location.course_accuracy,
+            speed_accuracy: location.speed_accuracy,
+            raw,
+            created_date: Default::default(),
+        }
+    }
+
     pub async fn create(&mut self, db: &DbPool) -> Result<(), Error> {
-        *self = (&sqlx::query("INSERT INTO location (client_uuid, group_uuid, longitude, latitude, location_date, raw) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *")
+        *self = (&sqlx::query("INSERT INTO location (client_uuid, group_uuid, longitude, latitude, location_date, precision_mode, precision, altitude, floor, course, speed, horizontal_accuracy, vertical_accuracy, course_accuracy, speed_accuracy, raw) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) RETURNING *")
             .bind(self.client_uuid)
             .bind(self.group_uuid)
             .bind(self.longitude)
             .bind(self.latitude)
             .bind(self.location_date.timestamp_millis())
+            .bind(self.precision.mode())
+            .bind(self.precision.grid_metres())
+            .bind(self.altitude)
+            .bind(self.floor)
+            .bind(self.course)
+            .bind(self.speed)
+            .bind(self.horizontal_accuracy)
+            .bind(self.vertical_accuracy)
+            .bind(self.course_accuracy)
+            .bind(self.speed_accuracy)
             .bind(&self.raw)
             .fetch_one(db).await?).into();
         Ok(())
@@ -65,6 +128,65 @@ impl Location {
         Ok(locations.iter().map(Location::from).collect())
     }
 
+    // oldest first
+    pub async fn range(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        client_uuid: &Uuid,
+        from: &NaiveDateTime,
+        to: &NaiveDateTime,
+    ) -> Result<Vec<Location>, sqlx::Error> {
+        let locations = sqlx::query("SELECT * FROM location WHERE location_date BETWEEN $1 AND $2 AND group_uuid = $3 AND client_uuid = $4 ORDER BY location_date ASC")
+            .bind(from.timestamp_millis())
+            .bind(to.timestamp_millis())
+            .bind(group_uuid)
+            .bind(client_uuid)
+            .fetch_all(db)
+            .await?;
+        Ok(locations.iter().map(Location::from).collect())
+    }
+
+    // sqlite returns the other columns of the row that has the MAX(location_date) of each client
+    pub async fn latest(
+        db: &DbPool,
+        group_uuid: &Uuid,
+    ) -> Result<Vec<LatestLocation>, sqlx::Error> {
+        let locations = sqlx::query("SELECT location.*, \"user\".username, \"user\".name AS user_name, MAX(location.location_date) FROM location LEFT JOIN client ON client.uuid = location.client_uuid LEFT JOIN \"user\" ON \"user\".uuid = client.user_uuid WHERE location.group_uuid = $1 GROUP BY location.client_uuid")
+            .bind(group_uuid)
+            .fetch_all(db)
+            .await?;
+        Ok(locations.iter().map(LatestLocation::from).collect())
+    }
+
+    pub async fn clear_raw(db: &DbPool) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE location SET raw = NULL WHERE raw IS NOT NULL")
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    // keeps a single location per client, group and `interval` of the locations before `before`
+    pub async fn downsample(
+        db: &DbPool,
+        before: &NaiveDateTime,
+        interval: Duration,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM location WHERE location_date < $1 AND id NOT IN (SELECT MIN(id) FROM location WHERE location_date < $1 GROUP BY group_uuid, client_uuid, location_date / $2)")
+            .bind(before.timestamp_millis())
+            .bind(interval.as_millis() as i64)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_before(db: &DbPool, before: &NaiveDateTime) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM location WHERE location_date < $1")
+            .bind(before.timestamp_millis())
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
     pub async fn count_query(
         db: &SqlitePool,
         group_uuid: &Uuid,
@@ -83,3 +205,39 @@ impl Location {
         Ok(count)
     }
 }
+
+// the most recent location of a client with the user it belongs to, if we have them cached
+pub struct LatestLocation {
+    pub location: Location,
+    pub username: Option<String>,
+    pub name: Option<String>,
+}
+
+impl From<&SqliteRow> for LatestLocation {
+    fn from(row: &SqliteRow) -> Self {
+        Self {
+            location: row.into(),
+            username: row.get("username"),
+            name: row.get("user_name"),
+        }
+    }
+}
+
+impl From<Location> for LocationMessage {
+    fn from(location: Location) -> Self {
+        Self {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            timestamp: location.location_date.timestamp_millis(),
+            precision: location.precision,
+            altitude: location.altitude,
+            floor: location.floor,
+            course: location.course,
+            speed: location.speed,
+            horizontal_accuracy: location.horizontal_accuracy,
+            vertical_accuracy: location.vertical_accuracy,
+            course_accuracy: location.course_accuracy,
+            speed_accuracy: location.speed_accuracy,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::models::get_real;
+use crate::types::DbPool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use uuid::Uuid;
+
+// a stay or a trip of a client, computed from its locations
+pub struct LocationSegment {
+    pub id: i32,
+    pub group_uuid: Uuid,
+    pub client_uuid: Uuid,
+    // false if this is a trip
+    pub stay: bool,
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+    // the centre of a stay, null for trips
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    // metres travelled, 0 for stays
+    pub distance: f64,
+}
+
+impl From<&SqliteRow> for LocationSegment {
+    fn from(row: &SqliteRow) -> Self {
+        let start_date: i64 = row.get("start_date");
+        let end_date: i64 = row.get("end_date");
+        Self {
+            id: row.get("id"),
+            group_uuid: row.get("group_uuid"),
+            client_uuid: row.get("client_uuid"),
+            stay: row.get("stay"),
+            start_date: NaiveDateTime::from_timestamp_millis(start_date).unwrap(),
+            end_date: NaiveDateTime::from_timestamp_millis(end_date).unwrap(),
+            latitude: get_real(row, "latitude"),
+            longitude: get_real(row, "longitude"),
+            distance: get_real(row, "distance"),
+        }
+    }
+}
+
+impl LocationSegment {
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        self.id = sqlx::query("INSERT INTO location_segment (group_uuid, client_uuid, stay, start_date, end_date, latitude, longitude, distance) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(self.group_uuid)
+            .bind(self.client_uuid)
+            .bind(self.stay)
+            .bind(self.start_date.timestamp_millis())
+            .bind(self.end_date.timestamp_millis())
+            .bind(self.latitude)
+            .bind(self.longitude)
+            .bind(self.distance)
+            .execute(db)
+            .await?
+            .last_insert_rowid() as i32;
+        Ok(())
+    }
+
+    pub async fn last(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        client_uuid: &Uuid,
+        stay: Option<bool>,
+    ) -> Result<Option<LocationSegment>, sqlx::Error> {
+        let segment = sqlx::query("SELECT * FROM location_segment WHERE group_uuid = $1 AND client_uuid = $2 AND ($3 IS NULL OR stay = $3) ORDER BY start_date DESC LIMIT 1")
+            .bind(group_uuid)
+            .bind(client_uuid)
+            .bind(stay)
+            .fetch_optional(db)
+            .await?
+            .as_ref()
+            .map(LocationSegment::from);
+        Ok(segment)
+    }
+
+    // the segments overlapping the range, oldest first
+    pub async fn query(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        client_uuid: &Uuid,
+        from: &NaiveDateTime,
+        to: &NaiveDateTime,
+    ) -> Result<Vec<LocationSegment>, sqlx::Error> {
+        let segments = sqlx::query("SELECT * FROM location_segment WHERE end_date >= $1 AND start_date <= $2 AND group_uuid = $3 AND client_uuid = $4 ORDER BY start_date ASC")
+            .bind(from.timestamp_millis())
+            .bind(to.timestamp_millis())
+            .bind(group_uuid)
+            .bind(client_uuid)
+            .fetch_all(db)
+            .await?;
+        Ok(segments.iter().map(LocationSegment::from).collect())
+    }
+
+    pub async fn delete_starting_from(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        client_uuid: &Uuid,
+        from: &NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM location_segment WHERE start_date >= $1 AND group_uuid = $2 AND client_uuid = $3")
+            .bind(from.timestamp_millis())
+            .bind(group_uuid)
+            .bind(client_uuid)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    // the segments a new location at `from` could change
+    pub async fn delete_ending_from(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        client_uuid: &Uuid,
+        from: &NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM location_segment WHERE end_date >= $1 AND group_uuid = $2 AND client_uuid = $3")
+            .bind(from.timestamp_millis())
+            .bind(group_uuid)
+            .bind(client_uuid)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_before(db: &DbPool, before: &NaiveDateTime) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM location_segment WHERE end_date < $1")
+            .bind(before.timestamp_millis())
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
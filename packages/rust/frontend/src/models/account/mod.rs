@@ -1,6 +1,12 @@
+pub mod chat_message;
 pub mod client;
 pub mod group;
 pub mod inbox;
 pub mod keystore;
 pub mod location;
+pub mod location_segment;
+pub mod outbox;
+pub mod place;
+pub mod place_event;
+pub mod receipt;
 pub mod user;
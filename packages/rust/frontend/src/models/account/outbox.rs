@@ -0,0 +1,206 @@
+use crate::types::DbPool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use sqlx::Row;
+use std::time::Duration;
+use uuid::Uuid;
+
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 30);
+// a message that failed this often is dropped, with the backoff that's about five hours
+pub const MAX_ATTEMPTS: i32 = 15;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutboxKind {
+    // a serialized application message, it is only encrypted when it is sent so it always uses the current epoch
+    Application,
+    // our pending commit, merged once the server accepts it
+    Commit,
+    Proposal,
+    // sent directly to `client_uuids` instead of the group
+    Welcome,
+    // a key package of this client asking the other members to add it back, also sent directly to `client_uuids`
+    RejoinRequest,
+}
+
+impl OutboxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxKind::Application => "application",
+            OutboxKind::Commit => "commit",
+            OutboxKind::Proposal => "proposal",
+            OutboxKind::Welcome => "welcome",
+            OutboxKind::RejoinRequest => "rejoin_request",
+        }
+    }
+
+    fn from_str(kind: &str) -> Self {
+        match kind {
+            "application" => OutboxKind::Application,
+            "commit" => OutboxKind::Commit,
+            "proposal" => OutboxKind::Proposal,
+            "welcome" => OutboxKind::Welcome,
+            "rejoin_request" => OutboxKind::RejoinRequest,
+            _ => panic!("unknown outbox kind: {}", kind),
+        }
+    }
+}
+
+// a message waiting to be sent, messages of a group are sent in the order they were queued
+pub struct Outbox {
+    pub id: i32,
+    pub group_uuid: Uuid,
+    pub kind: OutboxKind,
+    pub message: Vec<u8>,
+    // the recipients of a welcome, the clients to exclude otherwise
+    pub client_uuids: Vec<Uuid>,
+    pub attempts: i32,
+    pub next_attempt_date: NaiveDateTime,
+    // set once the message is dropped, it's kept until the user dismissed it
+    pub dropped_date: Option<NaiveDateTime>,
+    pub error: Option<String>,
+}
+
+impl From<&SqliteRow> for Outbox {
+    fn from(row: &SqliteRow) -> Self {
+        Self {
+            id: row.get("id"),
+            group_uuid: row.get("group_uuid"),
+            kind: OutboxKind::from_str(row.get("kind")),
+            message: row.get("message"),
+            client_uuids: serde_json::from_str(row.get("client_uuids")).unwrap(),
+            attempts: row.get("attempts"),
+            next_attempt_date: row.get("next_attempt_date"),
+            dropped_date: row.get("dropped_date"),
+            error: row.get("error"),
+        }
+    }
+}
+
+impl Outbox {
+    pub fn new(
+        group_uuid: Uuid,
+        kind: OutboxKind,
+        message: Vec<u8>,
+        client_uuids: Vec<Uuid>,
+    ) -> Self {
+        Self {
+            id: 0,
+            group_uuid,
+            kind,
+            message,
+            client_uuids,
+            attempts: 0,
+            next_attempt_date: Utc::now().naive_utc(),
+            dropped_date: None,
+            error: None,
+        }
+    }
+
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        // RETURNING leaves the insert uncommitted until the statement is reset, the next flush has to see the row right away
+        self.id = sqlx::query("INSERT INTO outbox (group_uuid, kind, message, client_uuids, attempts, next_attempt_date) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(self.group_uuid)
+            .bind(self.kind.as_str())
+            .bind(&self.message)
+            .bind(serde_json::to_string(&self.client_uuids).unwrap())
+            .bind(self.attempts)
+            .bind(self.next_attempt_date)
+            .execute(db)
+            .await?
+            .last_insert_rowid() as i32;
+        Ok(())
+    }
+
+    pub async fn from_group_uuid(
+        db: &DbPool,
+        group_uuid: Uuid,
+    ) -> Result<Vec<Outbox>, sqlx::Error> {
+        sqlx::query(
+            "SELECT * FROM outbox WHERE group_uuid = $1 AND dropped_date IS NULL ORDER BY id ASC",
+        )
+        .bind(group_uuid)
+        .map(|row: SqliteRow| Outbox::from(&row))
+        .fetch_all(db)
+        .await
+    }
+
+    // groups whose oldest message is due to be sent
+    pub async fn due_group_uuids(
+        db: &DbPool,
+        now: NaiveDateTime,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query("SELECT group_uuid FROM outbox WHERE id IN (SELECT MIN(id) FROM outbox WHERE dropped_date IS NULL GROUP BY group_uuid) AND next_attempt_date <= $1")
+            .bind(now)
+            .map(|row: SqliteRow| row.get("group_uuid"))
+            .fetch_all(db)
+            .await
+    }
+
+    // backs off exponentially, the messages queued after this one wait for it
+    pub async fn retry_later(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        let delay = BASE_RETRY_DELAY
+            .saturating_mul(2u32.saturating_pow(self.attempts as u32))
+            .min(MAX_RETRY_DELAY);
+        self.attempts += 1;
+        self.next_attempt_date =
+            NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + delay.as_secs() as i64, 0)
+                .unwrap();
+        sqlx::query("UPDATE outbox SET attempts = $1, next_attempt_date = $2 WHERE id = $3")
+            .bind(self.attempts)
+            .bind(self.next_attempt_date)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    // the message stops holding up the group's other messages, the user is told it wasn't sent
+    pub async fn drop(&mut self, db: &DbPool, error: String) -> Result<(), sqlx::Error> {
+        self.dropped_date = Some(Utc::now().naive_utc());
+        self.error = Some(error);
+        sqlx::query("UPDATE outbox SET dropped_date = $1, error = $2 WHERE id = $3")
+            .bind(self.dropped_date)
+            .bind(&self.error)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn all_dropped(db: &DbPool) -> Result<Vec<Outbox>, sqlx::Error> {
+        sqlx::query("SELECT * FROM outbox WHERE dropped_date IS NOT NULL ORDER BY id ASC")
+            .map(|row: SqliteRow| Outbox::from(&row))
+            .fetch_all(db)
+            .await
+    }
+
+    pub async fn exists(db: &DbPool, id: i32) -> Result<bool, sqlx::Error> {
+        Ok(sqlx::query("SELECT id FROM outbox WHERE id = $1")
+            .bind(id)
+            .fetch_optional(db)
+            .await?
+            .is_some())
+    }
+
+    pub async fn delete_by_id(db: &DbPool, id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM outbox WHERE id = $1")
+            .bind(id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_dropped_by_id(db: &DbPool, id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM outbox WHERE id = $1 AND dropped_date IS NOT NULL")
+            .bind(id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_all(db: &DbPool) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM outbox").execute(db).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::models::get_real;
+use crate::types::DbPool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+// a named area of a group, only known to this client
+pub struct Place {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub group_uuid: Uuid,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    // metres
+    pub radius: f64,
+}
+
+impl From<&SqliteRow> for Place {
+    fn from(row: &SqliteRow) -> Self {
+        Self {
+            id: row.get("id"),
+            uuid: row.get("uuid"),
+            group_uuid: row.get("group_uuid"),
+            name: row.get("name"),
+            latitude: get_real(row, "latitude"),
+            longitude: get_real(row, "longitude"),
+            radius: get_real(row, "radius"),
+        }
+    }
+}
+
+impl Place {
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        self.id = sqlx::query("INSERT INTO place (uuid, group_uuid, name, latitude, longitude, radius) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(self.uuid)
+            .bind(self.group_uuid)
+            .bind(&self.name)
+            .bind(self.latitude)
+            .bind(self.longitude)
+            .bind(self.radius)
+            .execute(db)
+            .await?
+            .last_insert_rowid() as i32;
+        Ok(())
+    }
+
+    pub async fn from_group_uuid(
+        db: &DbPool,
+        group_uuid: &Uuid,
+    ) -> Result<Vec<Place>, sqlx::Error> {
+        let places = sqlx::query("SELECT * FROM place WHERE group_uuid = $1 ORDER BY name ASC")
+            .bind(group_uuid)
+            .fetch_all(db)
+            .await?;
+        Ok(places.iter().map(Place::from).collect())
+    }
+
+    // the events of the place are deleted with it
+    pub async fn delete_by_uuid(db: &DbPool, uuid: &Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM place_event WHERE place_uuid = $1")
+            .bind(uuid)
+            .execute(db)
+            .await?;
+        sqlx::query("DELETE FROM place WHERE uuid = $1")
+            .bind(uuid)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
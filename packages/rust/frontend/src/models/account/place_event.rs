@@ -0,0 +1,77 @@
+use crate::types::DbPool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use uuid::Uuid;
+
+// a client entering or leaving a place
+pub struct PlaceEvent {
+    pub id: i32,
+    pub place_uuid: Uuid,
+    pub group_uuid: Uuid,
+    pub client_uuid: Uuid,
+    // false if the client left the place
+    pub entered: bool,
+    // the date of the location that triggered the event
+    pub event_date: NaiveDateTime,
+}
+
+impl From<&SqliteRow> for PlaceEvent {
+    fn from(row: &SqliteRow) -> Self {
+        let event_date: i64 = row.get("event_date");
+        let event_date = NaiveDateTime::from_timestamp_millis(event_date).unwrap();
+        Self {
+            id: row.get("id"),
+            place_uuid: row.get("place_uuid"),
+            group_uuid: row.get("group_uuid"),
+            client_uuid: row.get("client_uuid"),
+            entered: row.get("entered"),
+            event_date,
+        }
+    }
+}
+
+impl PlaceEvent {
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        self.id = sqlx::query("INSERT INTO place_event (place_uuid, group_uuid, client_uuid, entered, event_date) VALUES ($1, $2, $3, $4, $5)")
+            .bind(self.place_uuid)
+            .bind(self.group_uuid)
+            .bind(self.client_uuid)
+            .bind(self.entered)
+            .bind(self.event_date.timestamp_millis())
+            .execute(db)
+            .await?
+            .last_insert_rowid() as i32;
+        Ok(())
+    }
+
+    pub async fn latest(
+        db: &DbPool,
+        place_uuid: &Uuid,
+        client_uuid: &Uuid,
+    ) -> Result<Option<PlaceEvent>, sqlx::Error> {
+        let event = sqlx::query("SELECT * FROM place_event WHERE place_uuid = $1 AND client_uuid = $2 ORDER BY event_date DESC LIMIT 1")
+            .bind(place_uuid)
+            .bind(client_uuid)
+            .fetch_optional(db)
+            .await?
+            .as_ref()
+            .map(PlaceEvent::from);
+        Ok(event)
+    }
+
+    pub async fn query(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        before: &NaiveDateTime,
+        amount: u32,
+    ) -> Result<Vec<PlaceEvent>, sqlx::Error> {
+        let events = sqlx::query("SELECT * FROM place_event WHERE event_date < $1 AND group_uuid = $2 ORDER BY event_date DESC LIMIT $3")
+            .bind(before.timestamp_millis())
+            .bind(group_uuid)
+            .bind(amount)
+            .fetch_all(db)
+            .await?;
+        Ok(events.iter().map(PlaceEvent::from).collect())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::types::DbPool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use uuid::Uuid;
+
+// another member's client received, and maybe read, a message
+pub struct Receipt {
+    pub client_uuid: Uuid,
+    pub delivered_date: NaiveDateTime,
+    pub read_date: Option<NaiveDateTime>,
+}
+
+impl From<&SqliteRow> for Receipt {
+    fn from(row: &SqliteRow) -> Self {
+        let delivered_date: i64 = row.get("delivered_date");
+        let read_date: Option<i64> = row.get("read_date");
+        Self {
+            client_uuid: row.get("client_uuid"),
+            delivered_date: NaiveDateTime::from_timestamp_millis(delivered_date).unwrap(),
+            read_date: read_date.map(|date| NaiveDateTime::from_timestamp_millis(date).unwrap()),
+        }
+    }
+}
+
+impl Receipt {
+    // a read receipt also counts as delivered, the first date of each is kept
+    pub async fn record(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        message_uuid: &Uuid,
+        client_uuid: &Uuid,
+        date: &NaiveDateTime,
+        read: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO receipt (group_uuid, message_uuid, client_uuid, delivered_date, read_date) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (group_uuid, message_uuid, client_uuid) DO UPDATE SET delivered_date = MIN(receipt.delivered_date, excluded.delivered_date), read_date = COALESCE(receipt.read_date, excluded.read_date)")
+            .bind(group_uuid)
+            .bind(message_uuid)
+            .bind(client_uuid)
+            .bind(date.timestamp_millis())
+            .bind(read.then(|| date.timestamp_millis()))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn from_message_uuid(
+        db: &DbPool,
+        group_uuid: &Uuid,
+        message_uuid: &Uuid,
+    ) -> Result<Vec<Receipt>, sqlx::Error> {
+        let receipts =
+            sqlx::query("SELECT * FROM receipt WHERE group_uuid = $1 AND message_uuid = $2")
+                .bind(group_uuid)
+                .bind(message_uuid)
+                .fetch_all(db)
+                .await?;
+        Ok(receipts.iter().map(Receipt::from).collect())
+    }
+}
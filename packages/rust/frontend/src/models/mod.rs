@@ -1,3 +1,11 @@
+use sqlx::sqlite::{Sqlite, SqliteRow};
+use sqlx::{Decode, Row};
+
 pub mod account;
 pub mod global;
 pub mod kv;
+
+// sqlite stores whole numbers in REAL columns as integers, which sqlx refuses to decode as floats
+pub fn get_real<'r, T: Decode<'r, Sqlite>>(row: &'r SqliteRow, column: &str) -> T {
+    row.try_get_unchecked(column).unwrap()
+}
@@ -1,3 +1,4 @@
+use crate::application_message::Location;
 use crate::public::native_api::NativeApi;
 use crate::public::promise::Promise;
 use jni::objects::{GlobalRef, JObject};
@@ -77,6 +78,10 @@ impl NativeApi for AndroidApi {
     fn unsubscribe_from_location_updates(&self) -> Result<(), Self::Error> {
         Ok(())
     }
+
+    fn request_location(&self) -> Result<Option<Location>, Self::Error> {
+        Ok(None)
+    }
 }
 
 pub type DevicePromise = AndroidPromise;
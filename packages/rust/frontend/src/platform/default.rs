@@ -1,5 +1,7 @@
+use crate::application_message::Location;
 use crate::public::native_api::NativeApi;
 use crate::public::promise::Promise;
+use sqlx::types::chrono::Utc;
 use std::sync;
 use std::sync::mpsc::Receiver;
 use std::sync::Mutex;
@@ -56,6 +58,19 @@ impl NativeApi for DefaultApi {
     fn unsubscribe_from_location_updates(&self) -> Result<(), Self::Error> {
         Ok(())
     }
+
+    // there is no location here, the tests get a fixed one so location requests can be answered
+    fn request_location(&self) -> Result<Option<Location>, Self::Error> {
+        if cfg!(feature = "test-location") {
+            return Ok(Some(Location {
+                latitude: 0.0,
+                longitude: 0.0,
+                timestamp: Utc::now().timestamp_millis(),
+                ..Default::default()
+            }));
+        }
+        Ok(None)
+    }
 }
 
 pub mod export {
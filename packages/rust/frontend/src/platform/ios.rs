@@ -1,3 +1,4 @@
+use crate::application_message::Location;
 use crate::public::native_api::NativeApi;
 use crate::public::promise::{Callbacker, Promise};
 use log::warn;
@@ -15,6 +16,13 @@ extern "C" {
     pub fn has_location_permissions() -> bool;
     pub fn subscribe_to_location_updates(location_manager: *const c_void);
     pub fn unsubscribe_from_location_updates(location_manager: *const c_void);
+    pub fn request_location(
+        location_manager: *const c_void,
+        latitude: *mut f64,
+        longitude: *mut f64,
+        timestamp: *mut f64,
+        horizontal_accuracy: *mut f64,
+    ) -> bool;
     pub fn destroy_location_manager(location_manager: *const c_void);
 }
 
@@ -81,6 +89,29 @@ impl NativeApi for IOSApi {
         unsafe { unsubscribe_from_location_updates(self.location_manager) };
         Ok(())
     }
+    fn request_location(&self) -> Result<Option<Location>, Self::Error> {
+        let (mut latitude, mut longitude, mut timestamp, mut horizontal_accuracy) =
+            (0.0, 0.0, 0.0, 0.0);
+        let found = unsafe {
+            request_location(
+                self.location_manager,
+                &mut latitude,
+                &mut longitude,
+                &mut timestamp,
+                &mut horizontal_accuracy,
+            )
+        };
+        if !found {
+            return Ok(None);
+        }
+        Ok(Some(Location {
+            latitude,
+            longitude,
+            timestamp: timestamp as i64,
+            horizontal_accuracy: Some(horizontal_accuracy),
+            ..Default::default()
+        }))
+    }
 }
 
 impl Drop for IOSApi {
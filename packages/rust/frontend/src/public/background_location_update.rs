@@ -1,3 +1,4 @@
+use crate::application_message::Location;
 use crate::public::init::{create_frontend_instance, TokioThread};
 use crate::VIRTUAL_MEMORY;
 use log::warn;
@@ -19,6 +20,34 @@ pub struct LocationUpdate {
     pub speed_accuracy: Option<f64>,
 }
 
+// the platform reports a value it couldn't determine as a negative number
+fn reading(value: Option<f64>) -> Option<f64> {
+    value.filter(|value| *value >= 0.0)
+}
+
+impl From<&LocationUpdate> for Location {
+    fn from(update: &LocationUpdate) -> Self {
+        let vertical_accuracy = reading(update.vertical_accuracy);
+        Self {
+            latitude: update.latitude,
+            longitude: update.longitude,
+            timestamp: update.timestamp as i64,
+            precision: Default::default(),
+            // the altitude can be negative, it's only invalid when its accuracy is
+            altitude: update
+                .altitude
+                .filter(|_| update.vertical_accuracy.is_none() || vertical_accuracy.is_some()),
+            floor: update.floor,
+            course: reading(update.course),
+            speed: reading(update.speed),
+            horizontal_accuracy: reading(update.horizontal_accuracy),
+            vertical_accuracy,
+            course_accuracy: reading(update.course_accuracy),
+            speed_accuracy: reading(update.speed_accuracy),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct BackgroundLocationUpdateOptions {
     pub data_directory: String,
@@ -26,7 +55,6 @@ pub struct BackgroundLocationUpdateOptions {
 }
 
 pub fn background_location_update(options: BackgroundLocationUpdateOptions) -> bool {
-    // TODO: use an outbox mechanism to send the location updates
     let instance = {
         let instance = VIRTUAL_MEMORY
             .clone_iter()
@@ -41,6 +69,7 @@ pub fn background_location_update(options: BackgroundLocationUpdateOptions) -> b
                     .block_on(create_frontend_instance(options.data_directory, thread))
                     .unwrap(),
             );
+            instance.start_location_retention();
             VIRTUAL_MEMORY.push(instance.clone());
             instance
         }
@@ -50,26 +79,20 @@ pub fn background_location_update(options: BackgroundLocationUpdateOptions) -> b
         if !instance.logged_in().await {
             return false;
         }
-        let groups = instance.get_groups().await.unwrap();
-        // TODO: filter for groups that have location updates enabled
+        let groups = instance.get_location_sharing_groups().await.unwrap();
         warn!(
             "about to send location {:?} to groups: {:?}",
-            options.updates,
-            groups.iter().map(|g| g.uuid)
+            options.updates, groups
         );
-        for group in groups {
-            for update in &options.updates {
-                instance
-                    .send_location(
-                        group.uuid,
-                        update.longitude,
-                        update.latitude,
-                        update.timestamp as i64,
-                    )
-                    .await
-                    .map_err(|e| warn!("error sending location: {:?}", e))
-                    .unwrap();
-            }
+        for group_uuid in groups {
+            instance
+                .send_locations(
+                    group_uuid,
+                    options.updates.iter().map(Location::from).collect(),
+                )
+                .await
+                .map_err(|e| warn!("error sending location: {:?}", e))
+                .unwrap();
         }
         true
     })
@@ -75,8 +75,9 @@ pub fn init(promise: DevicePromise, json: String) -> Result<(), Error> {
             }
         }
         let frontend_instance =
-            create_frontend_instance(options.data_directory, tokio_thread).await?;
-        let address = VIRTUAL_MEMORY.push(Arc::new(frontend_instance));
+            Arc::new(create_frontend_instance(options.data_directory, tokio_thread).await?);
+        frontend_instance.start_location_retention();
+        let address = VIRTUAL_MEMORY.push(frontend_instance);
         Ok(address)
     }));
 
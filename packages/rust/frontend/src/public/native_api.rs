@@ -1,3 +1,5 @@
+use crate::application_message::Location;
+
 pub trait NativeApi {
     type Error;
 
@@ -6,4 +8,6 @@ pub trait NativeApi {
     fn has_location_permissions(&self) -> Result<bool, Self::Error>;
     fn subscribe_to_location_updates(&self) -> Result<(), Self::Error>;
     fn unsubscribe_from_location_updates(&self) -> Result<(), Self::Error>;
+    // a one-shot fix, blocks until the device has one, None if it couldn't get one
+    fn request_location(&self) -> Result<Option<Location>, Self::Error>;
 }
@@ -18,5 +18,7 @@ pub static MLS_GROUP_CONFIG: Lazy<MlsGroupConfig> = Lazy::new(|| {
             version: ProtocolVersion::default(),
         })
         .use_ratchet_tree_extension(true)
+        // members acknowledge messages while a commit may be on its way, their receipts are from the epoch before it
+        .max_past_epochs(3)
         .build()
 });
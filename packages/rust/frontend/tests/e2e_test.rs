@@ -1,11 +1,20 @@
+use common::base64::Base64;
 use frontend::application_message::Location;
+use frontend::application_message::LocationPrecision;
 use frontend::init;
-use frontend::js_interface::group::Group;
+use frontend::js_interface::chat::ChatMessage;
+use frontend::js_interface::group::{Group, LocationSettings};
+use frontend::js_interface::location::MemberLocation;
+use frontend::js_interface::place::{Place, PlaceEvent};
+use frontend::js_interface::receipt::{MessageReceipt, ReceiptSettings};
+use frontend::js_interface::retention::RetentionSettings;
+use frontend::js_interface::timeline::TimelineSegment;
 use frontend::public::init::InitOptions;
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
 use sqlx::types::chrono::NaiveDateTime;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 use thiserror::__private::PathAsDisplay;
 use uuid::Uuid;
@@ -73,6 +82,48 @@ pub fn create_instance(name: &str) -> Result<i32, String> {
     await_fn!(i32, String)
 }
 
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+// registers a user on a new instance and logs them in
+fn create_user(name: &str) -> (i32, Uuid) {
+    let instance = create_instance(name).unwrap();
+    let username = format!("{}username", name);
+    let password = format!("{}password", name);
+    call!(instance, register(username: username, password: password, name: name, email: format!("{}@email.com", name))).unwrap();
+    let user_uuid =
+        call!(instance, login(username_or_email: username, password: password) -> Result<Uuid, ()>)
+            .unwrap();
+    call!(instance, replace_key_packages()).unwrap();
+    (instance, user_uuid)
+}
+
+// the owner creates a group and adds the members one by one, everyone in the group processes each commit
+fn create_group_with(owner: i32, members: &[(i32, Uuid)]) -> Uuid {
+    let group_uuid = call!(owner, create_group() -> Result<Uuid, ()>).unwrap();
+    for (i, &(_, user_uuid)) in members.iter().enumerate() {
+        call!(owner, add_member(group_uuid: group_uuid, user_uuid: user_uuid)).unwrap();
+        for &(instance, _) in &members[..=i] {
+            call!(instance, receive_messages() -> Result<usize, ()>).unwrap();
+        }
+    }
+    group_uuid
+}
+
+// the client of a user with a single client, as seen by the instance
+fn client_of(instance: i32, group_uuid: Uuid, user_uuid: Uuid) -> Uuid {
+    let groups = call!(instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    let group = groups
+        .iter()
+        .find(|group| group.uuid == group_uuid)
+        .unwrap();
+    group.members[&user_uuid].clients[0]
+}
+
 #[test]
 pub fn test_basic() {
     reqwest::blocking::get("http://localhost:3000/reset").unwrap();
@@ -140,13 +191,10 @@ pub fn test_basic() {
     assert_eq!(locations.len(), num_locations as usize);
     assert_eq!(num_locations, 0);
 
-    let alice_location = (37.2431, -115.7930);
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64;
+    let alice_location = (-115.7930, 37.2431);
+    let now = now();
 
-    call!(alice_instance, send_location(group_uuid: group_uuid, longitude: alice_location.0, latitude: alice_location.1, timestamp: now)).unwrap();
+    call!(alice_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
 
     call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
@@ -158,13 +206,13 @@ pub fn test_basic() {
     assert_eq!(locations[0].latitude, alice_location.1);
     assert_eq!(locations[0].timestamp, now);
 
-    let bob_location = (32.0853, 34.7818);
+    let bob_location = (34.7818, 32.0853);
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis() as i64;
 
-    call!(bob_instance, send_location(group_uuid: group_uuid, longitude: bob_location.0, latitude: bob_location.1, timestamp: now)).unwrap();
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
 
     call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
@@ -177,6 +225,353 @@ pub fn test_basic() {
     assert_eq!(locations[0].timestamp, now);
 }
 
+#[test]
+pub fn test_location_details() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let now = now();
+
+    call!(alice_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": -115.7930, "latitude": 37.2431, "timestamp": now, "altitude": 1360.0, "speed": 1.5})) -> Result<Option<Uuid>, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let locations = call!(bob_instance, get_location(group_uuid: group_uuid, client: alice_client, before_timestamp: future, amount: 100) -> Result<Vec<Location>, ()>).unwrap();
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0].altitude, Some(1360.0));
+    assert_eq!(locations[0].speed, Some(1.5));
+    assert_eq!(locations[0].floor, None);
+}
+
+#[test]
+pub fn test_location_settings() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let alice = create_user("alice");
+    let alice_instance = alice.0;
+    let (bob_instance, bob_uuid) = create_user("bob");
+    let group_uuid = create_group_with(bob_instance, &[alice]);
+    let bob_client = client_of(alice_instance, group_uuid, bob_uuid);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let bob_location = (34.7818, 32.0853);
+    let now = now();
+
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let settings = call!(bob_instance, get_location_settings(group_uuid: group_uuid) -> Result<LocationSettings, ()>).unwrap();
+    assert!(settings.enabled);
+    assert_eq!(settings.paused_until, None);
+    assert_eq!(settings.precision, LocationPrecision::Exact);
+
+    assert!(call!(bob_instance, set_location_settings(group_uuid: group_uuid, settings: LocationSettings { enabled: true, paused_until: None, precision: LocationPrecision::Grid { metres: 0 } }) -> Result<(), Value>).is_err());
+    assert!(call!(bob_instance, set_location_settings(group_uuid: group_uuid, settings: LocationSettings { enabled: true, paused_until: Some(now - 1000), precision: LocationPrecision::Exact }) -> Result<(), Value>).is_err());
+    assert!(call!(bob_instance, set_location_settings(group_uuid: group_uuid, settings: LocationSettings { enabled: true, paused_until: Some(i64::MAX), precision: LocationPrecision::Exact }) -> Result<(), Value>).is_err());
+
+    let paused_until = now + 60 * 60 * 1000;
+    call!(bob_instance, set_location_settings(group_uuid: group_uuid, settings: LocationSettings { enabled: true, paused_until: Some(paused_until), precision: LocationPrecision::Exact })).unwrap();
+
+    let settings = call!(bob_instance, get_location_settings(group_uuid: group_uuid) -> Result<LocationSettings, ()>).unwrap();
+    assert_eq!(settings.paused_until, Some(paused_until));
+
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now + 1})) -> Result<Option<Uuid>, ()>).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let num_locations = call!(alice_instance, get_num_location(group_uuid: group_uuid, client: bob_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
+    assert_eq!(num_locations, 1);
+
+    call!(bob_instance, set_location_settings(group_uuid: group_uuid, settings: LocationSettings { enabled: true, paused_until: None, precision: LocationPrecision::Grid { metres: 1000 } })).unwrap();
+
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now + 2, "altitude": 10.0, "speed": 1.5, "horizontal_accuracy": 5.0})) -> Result<Option<Uuid>, ()>).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let locations = call!(alice_instance, get_location(group_uuid: group_uuid, client: bob_client, before_timestamp: future, amount: 100) -> Result<Vec<Location>, ()>).unwrap();
+    assert_eq!(locations.len(), 2);
+    assert_eq!(locations[0].timestamp, now + 2);
+    assert_eq!(
+        locations[0].precision,
+        LocationPrecision::Grid { metres: 1000 }
+    );
+    assert_ne!(locations[0].latitude, bob_location.1);
+    assert!((locations[0].latitude - bob_location.1).abs() < 0.01);
+    assert!((locations[0].longitude - bob_location.0).abs() < 0.01);
+    assert_eq!(locations[0].altitude, None);
+    assert_eq!(locations[0].speed, None);
+    assert_eq!(locations[0].horizontal_accuracy, Some(1000.0));
+    assert_eq!(locations[1].precision, LocationPrecision::Exact);
+
+    call!(bob_instance, set_location_settings(group_uuid: group_uuid, settings: LocationSettings { enabled: true, paused_until: None, precision: LocationPrecision::City })).unwrap();
+
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now + 3})) -> Result<Option<Uuid>, ()>).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let locations = call!(alice_instance, get_location(group_uuid: group_uuid, client: bob_client, before_timestamp: future, amount: 1) -> Result<Vec<Location>, ()>).unwrap();
+    assert_eq!(locations[0].timestamp, now + 3);
+    assert_eq!(locations[0].precision, LocationPrecision::City);
+    assert!((locations[0].latitude - bob_location.1).abs() < 0.1);
+}
+
+#[test]
+pub fn test_location_batch() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let alice_location = (-115.7930, 37.2431);
+    let now = now();
+
+    call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now},
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now + 1},
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now + 2}
+    ])) -> Result<Option<Uuid>, ()>)
+    .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let locations = call!(bob_instance, get_location(group_uuid: group_uuid, client: alice_client, before_timestamp: future, amount: 100) -> Result<Vec<Location>, ()>).unwrap();
+    assert_eq!(locations.len(), 3);
+    assert_eq!(locations[0].timestamp, now + 2);
+    assert_eq!(locations[1].timestamp, now + 1);
+    assert_eq!(locations[2].timestamp, now);
+}
+
+#[test]
+pub fn test_places() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let alice_location = (-115.7930, 37.2431);
+    let bob_location = (34.7818, 32.0853);
+    let now = now();
+
+    assert!(call!(bob_instance, create_place(group_uuid: group_uuid, name: "Area 51", latitude: alice_location.1, longitude: alice_location.0, radius: 0.0) -> Result<Uuid, Value>).is_err());
+    assert!(call!(bob_instance, create_place(group_uuid: group_uuid, name: "Area 51", latitude: alice_location.1, longitude: alice_location.0, radius: -500.0) -> Result<Uuid, Value>).is_err());
+    assert!(call!(bob_instance, create_place(group_uuid: group_uuid, name: "Area 51", latitude: 91.0, longitude: alice_location.0, radius: 500.0) -> Result<Uuid, Value>).is_err());
+    assert!(call!(bob_instance, create_place(group_uuid: Uuid::new_v4(), name: "Area 51", latitude: alice_location.1, longitude: alice_location.0, radius: 500.0) -> Result<Uuid, Value>).is_err());
+    let place_uuid = call!(bob_instance, create_place(group_uuid: group_uuid, name: "Area 51", latitude: alice_location.1, longitude: alice_location.0, radius: 500.0) -> Result<Uuid, ()>).unwrap();
+
+    let places =
+        call!(bob_instance, get_places(group_uuid: group_uuid) -> Result<Vec<Place>, ()>).unwrap();
+    assert_eq!(places.len(), 1);
+    assert_eq!(places[0].uuid, place_uuid);
+    assert_eq!(places[0].name, "Area 51");
+
+    call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"longitude": alice_location.0 + 0.001, "latitude": alice_location.1, "timestamp": now},
+        {"longitude": alice_location.0, "latitude": alice_location.1 + 0.001, "timestamp": now + 1},
+        {"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now + 2}
+    ])) -> Result<Option<Uuid>, ()>)
+    .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let events = call!(bob_instance, get_place_events(group_uuid: group_uuid, before_timestamp: future, amount: 100) -> Result<Vec<PlaceEvent>, ()>).unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].place_uuid, place_uuid);
+    assert_eq!(events[0].client_uuid, alice_client);
+    assert!(!events[0].entered);
+    assert_eq!(events[0].timestamp, now + 2);
+    assert!(events[1].entered);
+    assert_eq!(events[1].timestamp, now);
+
+    call!(bob_instance, delete_place(place_uuid: place_uuid)).unwrap();
+
+    let places =
+        call!(bob_instance, get_places(group_uuid: group_uuid) -> Result<Vec<Place>, ()>).unwrap();
+    assert!(places.is_empty());
+    let events = call!(bob_instance, get_place_events(group_uuid: group_uuid, before_timestamp: future, amount: 100) -> Result<Vec<PlaceEvent>, ()>).unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+pub fn test_retention() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let minute = 60 * 1000;
+    let now = now();
+
+    call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": now - 90 * minute},
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": now - 60 * minute},
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": now - 30 * minute},
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": now}
+    ])) -> Result<Option<Uuid>, ()>)
+    .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    // nothing is compacted until the user opts in
+    let settings =
+        call!(bob_instance, get_retention_settings() -> Result<RetentionSettings, ()>).unwrap();
+    assert!(!settings.keep_raw);
+    assert_eq!(settings.downsample_after_days, None);
+    assert_eq!(settings.delete_after_days, None);
+
+    call!(bob_instance, compact_locations()).unwrap();
+    let num_locations = call!(bob_instance, get_num_location(group_uuid: group_uuid, client: alice_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
+    assert_eq!(num_locations, 4);
+
+    call!(bob_instance, set_retention_settings(settings: RetentionSettings { keep_raw: false, downsample_after_days: Some(0), downsample_interval_minutes: 24 * 60, delete_after_days: None })).unwrap();
+    call!(bob_instance, compact_locations()).unwrap();
+
+    // all of alice's locations are from the last two hours
+    let num_locations = call!(bob_instance, get_num_location(group_uuid: group_uuid, client: alice_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
+    assert!(num_locations <= 2);
+
+    call!(bob_instance, set_retention_settings(settings: RetentionSettings { keep_raw: false, downsample_after_days: None, downsample_interval_minutes: 15, delete_after_days: Some(0) })).unwrap();
+    call!(bob_instance, compact_locations()).unwrap();
+
+    let num_locations = call!(bob_instance, get_num_location(group_uuid: group_uuid, client: alice_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
+    assert_eq!(num_locations, 0);
+}
+
+#[test]
+pub fn test_latest_locations() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let alice_location = (-115.7930, 37.2431);
+    let bob_location = (34.7818, 32.0853);
+    let now = now();
+
+    call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now},
+        {"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now + 1}
+    ])) -> Result<Option<Uuid>, ()>)
+    .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let latest = call!(bob_instance, get_latest_locations(group_uuid: group_uuid) -> Result<Vec<MemberLocation>, ()>).unwrap();
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[0].user_uuid, alice_uuid);
+    assert_eq!(latest[0].username.as_deref(), Some("aliceusername"));
+    assert_eq!(latest[0].client_uuid, alice_client);
+    assert_eq!(latest[0].location.timestamp, now + 1);
+    assert_eq!(latest[0].location.longitude, bob_location.0);
+}
+
+#[test]
+pub fn test_export_locations() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let alice_location = (-115.7930, 37.2431);
+    let now = now();
+
+    call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now},
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now + 1},
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now + 2},
+        {"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now + 3}
+    ])) -> Result<Option<Uuid>, ()>)
+    .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let gpx = call!(bob_instance, export_locations(group_uuid: group_uuid, client: alice_client, from_timestamp: now, to_timestamp: now + 2, format: "gpx") -> Result<String, ()>).unwrap();
+    assert!(gpx.starts_with("<?xml"));
+    assert_eq!(gpx.matches("<trkpt ").count(), 3);
+
+    let geojson = call!(bob_instance, export_locations(group_uuid: group_uuid, client: alice_client, from_timestamp: now, to_timestamp: now + 2, format: "geojson") -> Result<String, ()>).unwrap();
+    let geojson: Value = serde_json::from_str(&geojson).unwrap();
+    assert_eq!(geojson["type"], "FeatureCollection");
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), 3);
+    assert_eq!(features[0]["geometry"]["coordinates"][0], alice_location.0);
+    assert_eq!(features[0]["geometry"]["coordinates"][1], alice_location.1);
+
+    assert!(call!(bob_instance, export_locations(group_uuid: group_uuid, client: alice_client, from_timestamp: 0, to_timestamp: future, format: "kml") -> Result<String, Value>).is_err());
+}
+
+#[test]
+pub fn test_timeline() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, alice_uuid) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+    let alice_client = client_of(bob_instance, group_uuid, alice_uuid);
+
+    let minute = 60 * 1000;
+    let home = now() - 2 * 60 * minute;
+    call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": home},
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": home + 5 * minute},
+        {"latitude": 40.0001, "longitude": -74.0, "timestamp": home + 10 * minute},
+        {"latitude": 40.0, "longitude": -74.0, "timestamp": home + 15 * minute},
+        {"latitude": 40.05, "longitude": -74.0, "timestamp": home + 25 * minute},
+        {"latitude": 40.1, "longitude": -74.0, "timestamp": home + 35 * minute},
+        {"latitude": 40.1, "longitude": -74.0, "timestamp": home + 45 * minute}
+    ])) -> Result<Option<Uuid>, ()>)
+    .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let timeline = call!(bob_instance, get_timeline(group_uuid: group_uuid, client: alice_client, from_timestamp: home + 1, to_timestamp: home + 40 * minute) -> Result<Vec<TimelineSegment>, ()>).unwrap();
+    assert_eq!(timeline.len(), 3);
+    assert!(timeline[0].stay);
+    assert_eq!(timeline[0].start_timestamp, home);
+    assert_eq!(timeline[0].duration, 15 * minute);
+    assert!((timeline[0].latitude.unwrap() - 40.0).abs() < 0.001);
+    assert!(!timeline[1].stay);
+    assert_eq!(timeline[1].start_timestamp, home + 15 * minute);
+    assert_eq!(timeline[1].end_timestamp, home + 35 * minute);
+    assert!((timeline[1].distance - 11_119.0).abs() < 100.0);
+    assert!(timeline[2].stay);
+    assert_eq!(timeline[2].end_timestamp, home + 45 * minute);
+
+    call!(alice_instance, send_location(group_uuid: group_uuid, location: json!({"latitude": 40.1, "longitude": -74.0, "timestamp": home + 60 * minute})) -> Result<Option<Uuid>, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let timeline = call!(bob_instance, get_timeline(group_uuid: group_uuid, client: alice_client, from_timestamp: home + 1, to_timestamp: home + 40 * minute) -> Result<Vec<TimelineSegment>, ()>).unwrap();
+    assert_eq!(timeline.len(), 3);
+    assert_eq!(timeline[2].end_timestamp, home + 60 * minute);
+}
+
 #[test]
 pub fn test_full() {
     reqwest::blocking::get("http://localhost:3000/reset").unwrap();
@@ -223,13 +618,13 @@ pub fn test_full() {
 
     let future = NaiveDateTime::MAX.timestamp_millis();
 
-    let alice_location = (37.2431, -115.7930);
+    let alice_location = (-115.7930, 37.2431);
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis() as i64;
 
-    call!(alice_instance, send_location(group_uuid: group_uuid, longitude: alice_location.0, latitude: alice_location.1, timestamp: now)).unwrap();
+    call!(alice_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": alice_location.0, "latitude": alice_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
 
     call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
@@ -241,13 +636,13 @@ pub fn test_full() {
     let num_locations = call!(charlie_instance, get_num_location(group_uuid: group_uuid, client: alice_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
     assert_eq!(num_locations, 1);
 
-    let bob_location = (32.0853, 34.7818);
+    let bob_location = (34.7818, 32.0853);
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis() as i64;
 
-    call!(bob_instance, send_location(group_uuid: group_uuid, longitude: bob_location.0, latitude: bob_location.1, timestamp: now)).unwrap();
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
 
     call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
@@ -268,13 +663,13 @@ pub fn test_full() {
     call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
     call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
-    let bob_location = (32.0853, 34.7818);
+    let bob_location = (34.7818, 32.0853);
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis() as i64;
 
-    call!(bob_instance, send_location(group_uuid: group_uuid, longitude: bob_location.0, latitude: bob_location.1, timestamp: now)).unwrap();
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
 
     call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
@@ -292,13 +687,13 @@ pub fn test_full() {
     call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
     call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
 
-    let bob_location = (32.0853, 34.7818);
+    let bob_location = (34.7818, 32.0853);
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis() as i64;
 
-    call!(bob_instance, send_location(group_uuid: group_uuid, longitude: bob_location.0, latitude: bob_location.1, timestamp: now)).unwrap();
+    call!(bob_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": bob_location.0, "latitude": bob_location.1, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap();
 
     call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
     call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
@@ -350,7 +745,7 @@ pub fn test_full() {
     // group status
     let group_name = "test group";
 
-    call!(charlie_instance, update_group(group_uuid: group_uuid, name: Some(group_name)) -> Result<(), ()>).unwrap();
+    call!(charlie_instance, update_group(group_uuid: group_uuid, name: Some(group_name)) -> Result<Uuid, ()>).unwrap();
 
     let groups = call!(charlie_instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
     assert_eq!(groups[0].name, Some(group_name.to_string()));
@@ -371,7 +766,7 @@ pub fn test_full() {
     .unwrap();
 
     // send the status update
-    call!(bob_instance, send_group_status(group_uuid: group_uuid) -> Result<(), ()>).unwrap();
+    call!(bob_instance, send_group_status(group_uuid: group_uuid) -> Result<Uuid, ()>).unwrap();
 
     // receive messages
     call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
@@ -379,3 +774,396 @@ pub fn test_full() {
     let groups = call!(alice_instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
     assert_eq!(groups[0].name, Some(group_name.to_string()));
 }
+
+#[test]
+pub fn test_group_image() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, _) = create_user("alice");
+    let bob = create_user("bob");
+    let bob_instance = bob.0;
+    let group_uuid = create_group_with(alice_instance, &[bob]);
+
+    let mut small_image = b"\x89PNG\r\n\x1a\n".to_vec();
+    small_image.extend([1; 100]);
+    call!(alice_instance, set_group_image(group_uuid: group_uuid, image: Base64(small_image.clone())) -> Result<Uuid, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let groups = call!(bob_instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    assert_eq!(groups[0].image.as_ref(), Some(&small_image));
+
+    // too large to be sent inline, it's uploaded as an attachment
+    let mut large_image = vec![0xff, 0xd8, 0xff];
+    large_image.extend([2; 100 * 1024]);
+    call!(alice_instance, set_group_image(group_uuid: group_uuid, image: Base64(large_image.clone())) -> Result<Uuid, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let groups = call!(bob_instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    assert_eq!(groups[0].image.as_ref(), Some(&large_image));
+
+    // renaming the group keeps the image
+    call!(alice_instance, update_group(group_uuid: group_uuid, name: "Bubble") -> Result<Uuid, ()>)
+        .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let groups = call!(bob_instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    assert_eq!(groups[0].name.as_deref(), Some("Bubble"));
+    assert_eq!(groups[0].image.as_ref(), Some(&large_image));
+
+    // the attachment was uploaded before charlie joined, so it's uploaded again for him
+    let (charlie_instance, charlie_uuid) = create_user("charlie");
+    call!(alice_instance, add_member(group_uuid: group_uuid, user_uuid: charlie_uuid)).unwrap();
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_instance, send_group_status(group_uuid: group_uuid) -> Result<Uuid, ()>).unwrap();
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    for instance in [bob_instance, charlie_instance] {
+        let groups = call!(instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+        assert_eq!(groups[0].name.as_deref(), Some("Bubble"));
+        assert_eq!(groups[0].image.as_ref(), Some(&large_image));
+    }
+
+    assert!(call!(alice_instance, set_group_image(group_uuid: group_uuid, image: Base64(vec![1, 2, 3])) -> Result<Uuid, Value>).is_err());
+}
+
+#[test]
+pub fn test_text_messages() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, _) = create_user("alice");
+    let bob = create_user("bob");
+    let charlie = create_user("charlie");
+    let (bob_instance, charlie_instance) = (bob.0, charlie.0);
+    let group_uuid = create_group_with(alice_instance, &[bob, charlie]);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+
+    let hello_uuid = call!(alice_instance, send_text(group_uuid: group_uuid, body: "hello", reply_to: None::<Uuid>) -> Result<Uuid, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    thread::sleep(Duration::from_millis(5));
+    call!(bob_instance, send_text(group_uuid: group_uuid, body: "hi alice", reply_to: hello_uuid) -> Result<Uuid, ()>).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    // our own messages are stored too
+    for instance in [alice_instance, bob_instance, charlie_instance] {
+        let messages = call!(instance, get_messages(group_uuid: group_uuid, before_timestamp: future, amount: 10) -> Result<Vec<ChatMessage>, ()>).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].body, "hi alice");
+        assert_eq!(messages[0].reply_to, Some(hello_uuid));
+        assert_eq!(messages[1].uuid, hello_uuid);
+        assert_eq!(messages[1].body, "hello");
+        assert_eq!(messages[1].reply_to, None);
+    }
+
+    let messages = call!(charlie_instance, get_messages(group_uuid: group_uuid, before_timestamp: future, amount: 1) -> Result<Vec<ChatMessage>, ()>).unwrap();
+    assert_eq!(messages.len(), 1);
+
+    assert!(call!(alice_instance, send_text(group_uuid: group_uuid, body: " ", reply_to: None::<Uuid>) -> Result<Uuid, Value>).is_err());
+}
+
+#[test]
+pub fn test_receipts() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, _) = create_user("alice");
+    let bob = create_user("bob");
+    let charlie = create_user("charlie");
+    let (bob_instance, bob_uuid) = bob;
+    let (charlie_instance, charlie_uuid) = charlie;
+    let group_uuid = create_group_with(alice_instance, &[bob, charlie]);
+
+    let hello_uuid = call!(alice_instance, send_text(group_uuid: group_uuid, body: "hello", reply_to: None::<Uuid>) -> Result<Uuid, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    // bob and charlie acknowledged hello when they received it
+    let receipts = call!(alice_instance, get_receipts(group_uuid: group_uuid, message_uuid: hello_uuid) -> Result<Vec<MessageReceipt>, ()>).unwrap();
+    assert_eq!(receipts.len(), 2);
+    assert!(receipts.iter().any(|r| r.user_uuid == bob_uuid));
+    assert!(receipts.iter().any(|r| r.user_uuid == charlie_uuid));
+    assert!(receipts
+        .iter()
+        .all(|r| r.delivered_timestamp.is_some() && r.read_timestamp.is_none()));
+
+    call!(charlie_instance, set_receipt_settings(settings: json!({"send_delivery_receipts": true, "send_read_receipts": false}))).unwrap();
+    let settings =
+        call!(charlie_instance, get_receipt_settings() -> Result<ReceiptSettings, ()>).unwrap();
+    assert!(!settings.send_read_receipts);
+
+    call!(bob_instance, mark_messages_read(group_uuid: group_uuid, message_uuids: vec![hello_uuid])).unwrap();
+    call!(charlie_instance, mark_messages_read(group_uuid: group_uuid, message_uuids: vec![hello_uuid])).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let receipts = call!(alice_instance, get_receipts(group_uuid: group_uuid, message_uuid: hello_uuid) -> Result<Vec<MessageReceipt>, ()>).unwrap();
+    let bob_receipt = receipts.iter().find(|r| r.user_uuid == bob_uuid).unwrap();
+    assert!(bob_receipt.read_timestamp.is_some());
+    let charlie_receipt = receipts
+        .iter()
+        .find(|r| r.user_uuid == charlie_uuid)
+        .unwrap();
+    assert!(charlie_receipt.delivered_timestamp.is_some());
+    assert!(charlie_receipt.read_timestamp.is_none());
+
+    // locations and statuses are acknowledged too
+    let now = now();
+    let location_uuid = call!(alice_instance, send_location(group_uuid: group_uuid, location: json!({"longitude": -115.7930, "latitude": 37.2431, "timestamp": now})) -> Result<Option<Uuid>, ()>).unwrap().unwrap();
+    let batch_uuid =
+        call!(alice_instance, send_locations(group_uuid: group_uuid, locations: json!([
+        {"longitude": -115.7930, "latitude": 37.2431, "timestamp": now + 1},
+        {"longitude": -115.7930, "latitude": 37.2431, "timestamp": now + 2}
+    ])) -> Result<Option<Uuid>, ()>)
+        .unwrap()
+        .unwrap();
+    let status_uuid = call!(alice_instance, update_group(group_uuid: group_uuid, name: "Bubble") -> Result<Uuid, ()>).unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    for message_uuid in [location_uuid, batch_uuid, status_uuid] {
+        let receipts = call!(alice_instance, get_receipts(group_uuid: group_uuid, message_uuid: message_uuid) -> Result<Vec<MessageReceipt>, ()>).unwrap();
+        assert_eq!(receipts.len(), 2);
+        assert!(receipts.iter().all(|r| r.delivered_timestamp.is_some()));
+    }
+}
+
+#[test]
+pub fn test_location_request() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let (alice_instance, _) = create_user("alice");
+    let bob = create_user("bob");
+    let charlie = create_user("charlie");
+    let (bob_instance, bob_uuid) = bob;
+    let (charlie_instance, charlie_uuid) = charlie;
+    let group_uuid = create_group_with(alice_instance, &[bob, charlie]);
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+
+    // only bob is asked, he replies with the location the device gave him
+    call!(alice_instance, request_member_location(group_uuid: group_uuid, user_uuid: bob_uuid))
+        .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let latest = call!(alice_instance, get_latest_locations(group_uuid: group_uuid) -> Result<Vec<MemberLocation>, ()>).unwrap();
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[0].user_uuid, bob_uuid);
+    assert_eq!(latest[0].location.latitude, 0.0);
+    assert_eq!(latest[0].location.longitude, 0.0);
+    let bob_client = latest[0].client_uuid;
+    let bob_locations = call!(alice_instance, get_num_location(group_uuid: group_uuid, client: bob_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
+
+    // bob answered less than a minute ago, charlie wasn't asked before
+    call!(alice_instance, request_member_location(group_uuid: group_uuid, user_uuid: None::<Uuid>))
+        .unwrap();
+
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(charlie_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let num_locations = call!(alice_instance, get_num_location(group_uuid: group_uuid, client: bob_client, from_timestamp: 0, to_timestamp: future) -> Result<i64, ()>).unwrap();
+    assert_eq!(num_locations, bob_locations);
+    let latest = call!(alice_instance, get_latest_locations(group_uuid: group_uuid) -> Result<Vec<MemberLocation>, ()>).unwrap();
+    assert_eq!(latest.len(), 2);
+    assert!(latest.iter().any(|l| l.user_uuid == charlie_uuid));
+}
+
+#[test]
+pub fn test_message_stream() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let alice_instance = create_instance("alice").unwrap();
+    let bob_instance = create_instance("bob").unwrap();
+
+    call!(alice_instance, register(username: "aliceusername", password: "alicepassword", name: "alice", email: "alice@email.com")).unwrap();
+    call!(bob_instance, register(username: "bobusername", password: "bobpassword", name: "bob", email: "bob@email.com")).unwrap();
+
+    call!(alice_instance, login(username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, ()>).unwrap();
+    let bob_uuid = call!(bob_instance, login(username_or_email: "bobusername", password: "bobpassword") -> Result<Uuid, ()>).unwrap();
+
+    call!(alice_instance, replace_key_packages()).unwrap();
+    call!(bob_instance, replace_key_packages()).unwrap();
+
+    call!(bob_instance, subscribe_to_messages()).unwrap();
+    // give the stream a moment to connect
+    thread::sleep(Duration::from_millis(500));
+
+    let group_uuid = call!(alice_instance, create_group() -> Result<Uuid, ()>).unwrap();
+    call!(
+        alice_instance,
+        add_member(group_uuid: group_uuid, user_uuid: bob_uuid)
+    )
+    .unwrap();
+
+    // bob never calls receive_messages, the welcome has to arrive over the stream
+    let mut groups = Vec::new();
+    for _ in 0..50 {
+        groups = call!(bob_instance, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+        if !groups.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].uuid, group_uuid);
+    assert_eq!(groups[0].members.len(), 2);
+
+    call!(bob_instance, unsubscribe_from_messages()).unwrap();
+}
+
+#[test]
+pub fn test_device_link() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let alice_instance = create_instance("alice").unwrap();
+    let bob_instance = create_instance("bob").unwrap();
+
+    call!(alice_instance, register(username: "aliceusername", password: "alicepassword", name: "alice", email: "alice@email.com")).unwrap();
+    call!(bob_instance, register(username: "bobusername", password: "bobpassword", name: "bob", email: "bob@email.com")).unwrap();
+
+    let alice_uuid = call!(alice_instance, login(username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, ()>).unwrap();
+    let bob_uuid = call!(bob_instance, login(username_or_email: "bobusername", password: "bobpassword") -> Result<Uuid, ()>).unwrap();
+
+    call!(alice_instance, replace_key_packages()).unwrap();
+    call!(bob_instance, replace_key_packages()).unwrap();
+
+    let group_uuid = call!(alice_instance, create_group() -> Result<Uuid, ()>).unwrap();
+    call!(alice_instance, add_member(group_uuid: group_uuid, user_uuid: bob_uuid)).unwrap();
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    // the account was created on another device
+    let alice_phone = create_instance("alice_phone").unwrap();
+    assert!(call!(alice_phone, login(username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, Value>).is_err());
+    assert!(call!(alice_phone, finish_device_link() -> Result<Option<Uuid>, Value>).is_err());
+
+    let code = call!(alice_phone, start_device_link(username_or_email: "aliceusername", password: "alicepassword") -> Result<String, ()>).unwrap();
+    let linked = call!(alice_phone, finish_device_link() -> Result<Option<Uuid>, ()>).unwrap();
+    assert_eq!(linked, None);
+
+    // the phone has no client yet
+    assert!(!call!(alice_instance, link_device(code: code.clone()) -> Result<bool, ()>).unwrap());
+    let linked = call!(alice_phone, finish_device_link() -> Result<Option<Uuid>, ()>).unwrap();
+    assert_eq!(linked, Some(alice_uuid));
+    // any of our devices adds the new client to the groups it's missing from
+    let synced = call!(alice_instance, sync_clients() -> Result<usize, ()>).unwrap();
+    assert_eq!(synced, 1);
+    let synced = call!(alice_instance, sync_clients() -> Result<usize, ()>).unwrap();
+    assert_eq!(synced, 0);
+    assert!(call!(alice_instance, link_device(code: code.clone()) -> Result<bool, ()>).unwrap());
+    // the link is deleted once it's done
+    assert!(call!(alice_instance, link_device(code: code) -> Result<bool, Value>).is_err());
+
+    call!(alice_phone, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let groups = call!(alice_phone, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].uuid, group_uuid);
+    assert_eq!(groups[0].members.len(), 2);
+
+    call!(bob_instance, send_text(group_uuid: group_uuid, body: "hi alice", reply_to: None::<Uuid>) -> Result<Uuid, ()>).unwrap();
+
+    call!(alice_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_phone, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    for instance in [alice_instance, alice_phone] {
+        let messages = call!(instance, get_messages(group_uuid: group_uuid, before_timestamp: future, amount: 10) -> Result<Vec<ChatMessage>, ()>).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].body, "hi alice");
+    }
+
+    // the phone logs in like the device the account was created on now
+    call!(alice_phone, logout()).unwrap();
+    let logged_in = call!(alice_phone, login(username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, ()>).unwrap();
+    assert_eq!(logged_in, alice_uuid);
+}
+
+#[test]
+pub fn test_backup_restore() {
+    reqwest::blocking::get("http://localhost:3000/reset").unwrap();
+
+    let alice_instance = create_instance("alice").unwrap();
+    let bob_instance = create_instance("bob").unwrap();
+
+    call!(alice_instance, register(username: "aliceusername", password: "alicepassword", name: "alice", email: "alice@email.com")).unwrap();
+    call!(bob_instance, register(username: "bobusername", password: "bobpassword", name: "bob", email: "bob@email.com")).unwrap();
+
+    let alice_uuid = call!(alice_instance, login(username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, ()>).unwrap();
+    let bob_uuid = call!(bob_instance, login(username_or_email: "bobusername", password: "bobpassword") -> Result<Uuid, ()>).unwrap();
+
+    call!(alice_instance, replace_key_packages()).unwrap();
+    call!(bob_instance, replace_key_packages()).unwrap();
+
+    let group_uuid = call!(alice_instance, create_group() -> Result<Uuid, ()>).unwrap();
+    call!(alice_instance, add_member(group_uuid: group_uuid, user_uuid: bob_uuid)).unwrap();
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_instance, send_text(group_uuid: group_uuid, body: "before the backup", reply_to: None::<Uuid>) -> Result<Uuid, ()>).unwrap();
+
+    assert!(call!(alice_instance, export_backup(passphrase: "") -> Result<Base64, Value>).is_err());
+    let backup =
+        call!(alice_instance, export_backup(passphrase: "correct horse") -> Result<Base64, ()>)
+            .unwrap();
+
+    // the phone was lost, the backup is restored on a new one
+    let alice_restored = create_instance("alice_restored").unwrap();
+    assert!(call!(alice_restored, restore_backup(backup: backup.clone(), passphrase: "wrong horse", username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, Value>).is_err());
+    // the backup belongs to alice
+    assert!(call!(alice_restored, restore_backup(backup: backup.clone(), passphrase: "correct horse", username_or_email: "bobusername", password: "bobpassword") -> Result<Uuid, Value>).is_err());
+    let restored = call!(alice_restored, restore_backup(backup: backup.clone(), passphrase: "correct horse", username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, ()>).unwrap();
+    assert_eq!(restored, alice_uuid);
+    assert!(call!(alice_restored, restore_backup(backup: backup, passphrase: "correct horse", username_or_email: "aliceusername", password: "alicepassword") -> Result<Uuid, Value>).is_err());
+
+    let future = NaiveDateTime::MAX.timestamp_millis();
+    let messages = call!(alice_restored, get_messages(group_uuid: group_uuid, before_timestamp: future, amount: 10) -> Result<Vec<ChatMessage>, ()>).unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].body, "before the backup");
+
+    let groups = call!(alice_restored, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    assert_eq!(groups.len(), 1);
+    assert!(groups[0].out_of_sync);
+
+    // restoring asked bob to add the restored client, a second request doesn't add it twice
+    let asked = call!(alice_restored, request_rejoin() -> Result<usize, ()>).unwrap();
+    assert_eq!(asked, 1);
+    // syncing only adds and removes our own clients
+    let synced = call!(bob_instance, sync_clients() -> Result<usize, ()>).unwrap();
+    assert_eq!(synced, 0);
+    // bob removes the lost client with it
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_restored, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let groups = call!(alice_restored, get_groups() -> Result<Vec<Group>, ()>).unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].uuid, group_uuid);
+    assert!(!groups[0].out_of_sync);
+    assert_eq!(groups[0].members[&alice_uuid].clients.len(), 1);
+
+    call!(bob_instance, send_text(group_uuid: group_uuid, body: "welcome back", reply_to: None::<Uuid>) -> Result<Uuid, ()>).unwrap();
+    call!(alice_restored, receive_messages() -> Result<usize, ()>).unwrap();
+    call!(alice_restored, send_text(group_uuid: group_uuid, body: "thanks", reply_to: None::<Uuid>) -> Result<Uuid, ()>).unwrap();
+    call!(bob_instance, receive_messages() -> Result<usize, ()>).unwrap();
+
+    let messages = call!(alice_restored, get_messages(group_uuid: group_uuid, before_timestamp: future, amount: 10) -> Result<Vec<ChatMessage>, ()>).unwrap();
+    assert_eq!(messages.len(), 3);
+    let messages = call!(bob_instance, get_messages(group_uuid: group_uuid, before_timestamp: future, amount: 10) -> Result<Vec<ChatMessage>, ()>).unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0].body, "thanks");
+    assert_eq!(messages[2].body, "before the backup");
+}
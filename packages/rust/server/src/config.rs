@@ -7,6 +7,30 @@ pub struct Config {
     pub api_key_check: String,
     pub sender_email: String,
     pub debug_mode: bool,
+    pub message_ttl: i64, // seconds an undelivered message is kept before it expires
+    pub blob_ttl: i64,    // seconds a blob is kept if not every recipient fetches it
+}
+
+const DEFAULT_TTL: i64 = 60 * 60 * 24 * 30;
+// keeps the expiry of anything stored well within the range of a timestamp
+const MAX_TTL: i64 = 60 * 60 * 24 * 365 * 10;
+
+// a ttl in seconds from the environment, clamped to at least a second and at most MAX_TTL
+fn ttl(name: &str) -> i64 {
+    let ttl: i64 = match env::var(name) {
+        Ok(ttl) => ttl
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number of seconds, got {:?}", name, ttl)),
+        Err(_) => return DEFAULT_TTL,
+    };
+    let clamped = ttl.clamp(1, MAX_TTL);
+    if clamped != ttl {
+        eprintln!(
+            "{} of {} seconds is out of range, using {}",
+            name, ttl, clamped
+        );
+    }
+    clamped
 }
 
 pub static CONFIG: Lazy<Config> = Lazy::new(|| Config {
@@ -15,4 +39,6 @@ pub static CONFIG: Lazy<Config> = Lazy::new(|| Config {
     api_key_check: env::var("SENDGRID_API_KEY").unwrap_or_default(), // pull api key from env. variables
     sender_email: env::var("SENDER_EMAIL").unwrap_or_default(),
     debug_mode: env::var("DEBUG_MODE").is_ok(),
+    message_ttl: ttl("MESSAGE_TTL"),
+    blob_ttl: ttl("BLOB_TTL"),
 });
@@ -1,14 +1,50 @@
+use once_cell::sync::Lazy;
 use server::config::CONFIG;
+use server::models::blob::Blob;
+use server::models::device_link::DeviceLink;
+use server::models::message::Message;
 #[allow(unused_imports)]
 use server::services::email::{PrinterEmailService, SendGridEmailService};
+use server::services::push::PrinterPushService;
+use server::types::DbPool;
 #[allow(unused_imports)]
 use server::types::EmailServiceArc;
+use server::types::PushServiceArc;
 use server::{config, router};
 use sqlx::postgres::PgPoolOptions;
+use sqlx::types::chrono::Utc;
 use std::sync::Arc;
+use std::time::Duration;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+async fn purge_expired(pool: DbPool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match Message::purge_expired(&pool, Utc::now().naive_utc()).await {
+            Ok(0) => {}
+            Ok(purged) => println!("Purged {} expired messages.", purged),
+            Err(err) => eprintln!("Unable to purge expired messages: {}", err),
+        }
+        match Blob::purge_expired(&pool, Utc::now().naive_utc()).await {
+            Ok(0) => {}
+            Ok(purged) => println!("Purged {} expired blobs.", purged),
+            Err(err) => eprintln!("Unable to purge expired blobs: {}", err),
+        }
+        match DeviceLink::purge_expired(&pool, Utc::now().naive_utc()).await {
+            Ok(0) => {}
+            Ok(purged) => println!("Purged {} expired device links.", purged),
+            Err(err) => eprintln!("Unable to purge expired device links: {}", err),
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), ()> {
+    // an invalid config fails here rather than on the first request that reads it
+    Lazy::force(&CONFIG);
+
     let pool = PgPoolOptions::new()
         .max_connections(5)
         .connect(&config::CONFIG.db_url)
@@ -27,7 +63,12 @@ async fn main() -> Result<(), ()> {
     #[cfg(test)]
     let email_service = Arc::new(PrinterEmailService::default());
 
-    let router = router::router(pool, email_service);
+    // TODO send through APNs and FCM
+    let push_service: PushServiceArc = Arc::new(PrinterPushService::default());
+
+    tokio::spawn(purge_expired(pool.clone()));
+
+    let router = router::router(pool, email_service, push_service);
 
     axum::Server::bind(&CONFIG.listen_addr.parse().unwrap())
         .serve(router.into_make_service())
@@ -0,0 +1,91 @@
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use std::borrow::Borrow;
+
+use crate::types::DbPool;
+
+// an encrypted attachment, identified by the sha256 hash of its data
+// it's deleted once every recipient fetched it or when it expires
+pub struct Blob {
+    pub id: i32,
+    pub hash: Vec<u8>,
+    pub data: Vec<u8>,
+    pub user_id: i32,
+    pub expires: NaiveDateTime,
+    pub created: NaiveDateTime,
+}
+
+impl From<&PgRow> for Blob {
+    fn from(row: &PgRow) -> Self {
+        Blob {
+            id: row.get("id"),
+            hash: row.get("hash"),
+            data: row.get("data"),
+            user_id: row.get("user_id"),
+            expires: row.get("expires"),
+            created: row.get("created"),
+        }
+    }
+}
+
+impl Blob {
+    // uploading the same data again keeps the first upload, adds the recipients and extends its expiry
+    pub async fn create(&mut self, db: &DbPool, client_ids: &[i32]) -> Result<(), sqlx::Error> {
+        *self = sqlx::query(
+            "INSERT INTO blob (hash, data, user_id, expires) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (hash) DO UPDATE SET expires = GREATEST(blob.expires, $4) RETURNING *;",
+        )
+        .bind(&self.hash)
+        .bind(&self.data)
+        .bind(self.user_id)
+        .bind(self.expires)
+        .fetch_one(db)
+        .await?
+        .borrow()
+        .into();
+
+        sqlx::query("INSERT INTO blob_recipient (client_id, blob_id) SELECT * FROM UNNEST($1::int8[], $2::int8[]) ON CONFLICT DO NOTHING;")
+            .bind(client_ids)
+            .bind(&vec![self.id; client_ids.len()][..])
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn from_hash(db: &DbPool, hash: &[u8]) -> Result<Blob, sqlx::Error> {
+        Ok(sqlx::query("SELECT * FROM blob WHERE hash = $1;")
+            .bind(hash)
+            .fetch_one(db)
+            .await?
+            .borrow()
+            .into())
+    }
+
+    // deletes the blob once the last of its recipients fetched it, blobs without recipients are kept until they expire
+    pub async fn fetched(&self, db: &DbPool, client_id: i32) -> Result<(), sqlx::Error> {
+        let removed =
+            sqlx::query("DELETE FROM blob_recipient WHERE blob_id = $1 AND client_id = $2;")
+                .bind(self.id)
+                .bind(client_id)
+                .execute(db)
+                .await?
+                .rows_affected();
+        if removed == 0 {
+            return Ok(());
+        }
+        sqlx::query("DELETE FROM blob WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM blob_recipient WHERE blob_id = $1);")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn purge_expired(db: &DbPool, now: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query("DELETE FROM blob WHERE expires < $1;")
+            .bind(now)
+            .execute(db)
+            .await?
+            .rows_affected())
+    }
+}
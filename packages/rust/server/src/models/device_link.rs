@@ -0,0 +1,97 @@
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use std::borrow::Borrow;
+
+use crate::types::DbPool;
+
+// hands the user's identity key from a logged in device to a new one
+// the key is encrypted to the new device, the server only relays it
+pub struct DeviceLink {
+    pub id: i32,
+    pub user_id: i32,
+    pub code: String,
+    pub public_key: Vec<u8>,
+    pub encrypted_key: Option<Vec<u8>>,
+    // the client the new device created
+    pub client_id: Option<i32>,
+    pub expires: NaiveDateTime,
+    pub created: NaiveDateTime,
+}
+
+impl From<&PgRow> for DeviceLink {
+    fn from(row: &PgRow) -> Self {
+        DeviceLink {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            code: row.get("code"),
+            public_key: row.get("public_key"),
+            encrypted_key: row.get("encrypted_key"),
+            client_id: row.get("client_id"),
+            expires: row.get("expires"),
+            created: row.get("created"),
+        }
+    }
+}
+
+impl DeviceLink {
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        *self = sqlx::query(
+            "INSERT INTO device_link (user_id, code, public_key, expires) VALUES ($1, $2, $3, $4) RETURNING *;",
+        )
+        .bind(self.user_id)
+        .bind(&self.code)
+        .bind(&self.public_key)
+        .bind(self.expires)
+        .fetch_one(db)
+        .await?
+        .borrow()
+        .into();
+        Ok(())
+    }
+
+    // expired links are never returned
+    pub async fn from_code(
+        db: &DbPool,
+        user_id: i32,
+        code: &str,
+        now: NaiveDateTime,
+    ) -> Result<DeviceLink, sqlx::Error> {
+        Ok(sqlx::query(
+            "SELECT * FROM device_link WHERE user_id = $1 AND code = $2 AND expires > $3;",
+        )
+        .bind(user_id)
+        .bind(code)
+        .bind(now)
+        .fetch_one(db)
+        .await?
+        .borrow()
+        .into())
+    }
+
+    pub async fn update(&self, db: &DbPool) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE device_link SET encrypted_key = $1, client_id = $2 WHERE id = $3;")
+            .bind(&self.encrypted_key)
+            .bind(self.client_id)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, db: &DbPool) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM device_link WHERE id = $1;")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn purge_expired(db: &DbPool, now: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query("DELETE FROM device_link WHERE expires < $1;")
+            .bind(now)
+            .execute(db)
+            .await?
+            .rows_affected())
+    }
+}
@@ -0,0 +1,58 @@
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::types::Uuid;
+use sqlx::Row;
+
+use crate::types::DbPool;
+
+// the number of messages of a group that expired before a client fetched them
+pub struct ExpiredMessage {
+    pub id: i32,
+    pub client_id: i32,
+    pub group_id: Option<i32>,
+    pub group_uuid: Option<Uuid>,
+    pub amount: i32,
+    pub created: NaiveDateTime,
+}
+
+impl From<&PgRow> for ExpiredMessage {
+    fn from(row: &PgRow) -> Self {
+        ExpiredMessage {
+            id: row.get("id"),
+            client_id: row.get("client_id"),
+            group_id: row.get("group_id"),
+            group_uuid: row.get("group_uuid"),
+            amount: row.get("amount"),
+            created: row.get("created"),
+        }
+    }
+}
+
+impl ExpiredMessage {
+    pub async fn from_client_id(
+        db: &DbPool,
+        client_id: i32,
+    ) -> Result<Vec<ExpiredMessage>, sqlx::Error> {
+        Ok(sqlx::query(
+            "SELECT expired_message.*, \"group\".uuid AS group_uuid
+        FROM expired_message
+        LEFT JOIN \"group\" ON \"group\".id = expired_message.group_id
+        WHERE expired_message.client_id = $1 ORDER BY expired_message.id ASC;",
+        )
+        .bind(client_id)
+        .fetch_all(db)
+        .await?
+        .iter()
+        .map(|row| row.into())
+        .collect())
+    }
+
+    pub async fn delete_ids(ids: &[i32], client_id: i32, db: &DbPool) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM expired_message WHERE client_id = $1 AND id = ANY($2);")
+            .bind(client_id)
+            .bind(ids)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::types::Uuid;
+use sqlx::Row;
+use std::borrow::Borrow;
+
+use crate::models::client::Client;
+use crate::types::DbPool;
+
+pub struct Group {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub created: NaiveDateTime,
+}
+
+impl From<&PgRow> for Group {
+    fn from(row: &PgRow) -> Self {
+        Group {
+            id: row.get("id"),
+            uuid: row.get("uuid"),
+            created: row.get("created"),
+        }
+    }
+}
+
+impl Group {
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        *self = sqlx::query("INSERT INTO \"group\" (uuid) VALUES ($1) RETURNING *;")
+            .bind(self.uuid)
+            .fetch_one(db)
+            .await?
+            .borrow()
+            .into();
+
+        Ok(())
+    }
+
+    pub async fn from_uuid(db: &DbPool, uuid: &Uuid) -> Result<Group, sqlx::Error> {
+        Ok(sqlx::query("SELECT * FROM \"group\" WHERE uuid = $1;")
+            .bind(uuid)
+            .fetch_one(db)
+            .await?
+            .borrow()
+            .into())
+    }
+
+    pub async fn from_ids(db: &DbPool, ids: &[i32]) -> Result<Vec<Group>, sqlx::Error> {
+        // a bug of the parameter typechecking code requires all array parameters to be slices
+        Ok(sqlx::query("SELECT * FROM \"group\" WHERE id = ANY($1);")
+            .bind(ids)
+            .fetch_all(db)
+            .await?
+            .iter()
+            .map(|row| row.into())
+            .collect())
+    }
+
+    pub async fn members(&self, db: &DbPool) -> Result<Vec<Client>, sqlx::Error> {
+        Ok(sqlx::query(
+            "SELECT client.*
+        FROM client
+        INNER JOIN group_member ON client.id = group_member.client_id
+        WHERE group_member.group_id = $1;",
+        )
+        .bind(self.id)
+        .fetch_all(db)
+        .await?
+        .iter()
+        .map(|row| row.into())
+        .collect())
+    }
+
+    // replaces the current membership with `client_ids`
+    pub async fn set_members(&self, db: &DbPool, client_ids: &[i32]) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+        sqlx::query("DELETE FROM group_member WHERE group_id = $1;")
+            .bind(self.id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query("INSERT INTO group_member (group_id, client_id) SELECT * FROM UNNEST($1::int8[], $2::int8[]);")
+            .bind(&vec![self.id; client_ids.len()][..])
+            .bind(client_ids)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
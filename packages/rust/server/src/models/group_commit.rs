@@ -0,0 +1,66 @@
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use std::borrow::Borrow;
+
+use crate::types::DbPool;
+
+// the commit the server accepted for an epoch of a group, at most one exists per epoch
+pub struct GroupCommit {
+    pub id: i32,
+    pub group_id: i32,
+    pub epoch: i64,
+    pub commit: Vec<u8>,
+    pub created: NaiveDateTime,
+}
+
+impl From<&PgRow> for GroupCommit {
+    fn from(row: &PgRow) -> Self {
+        GroupCommit {
+            id: row.get("id"),
+            group_id: row.get("group_id"),
+            epoch: row.get("epoch"),
+            commit: row.get("commit"),
+            created: row.get("created"),
+        }
+    }
+}
+
+impl GroupCommit {
+    // returns false without inserting if a commit was already accepted for the epoch
+    pub async fn create(&mut self, db: &DbPool) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query(
+            "INSERT INTO group_commit (group_id, epoch, commit) VALUES ($1, $2, $3)
+        ON CONFLICT (group_id, epoch) DO NOTHING RETURNING *;",
+        )
+        .bind(self.group_id)
+        .bind(self.epoch)
+        .bind(&self.commit)
+        .fetch_optional(db)
+        .await?;
+
+        match row {
+            Some(row) => {
+                *self = row.borrow().into();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub async fn from_epoch(
+        db: &DbPool,
+        group_id: i32,
+        epoch: i64,
+    ) -> Result<GroupCommit, sqlx::Error> {
+        Ok(
+            sqlx::query("SELECT * FROM group_commit WHERE group_id = $1 AND epoch = $2;")
+                .bind(group_id)
+                .bind(epoch)
+                .fetch_one(db)
+                .await?
+                .borrow()
+                .into(),
+        )
+    }
+}
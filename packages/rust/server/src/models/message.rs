@@ -26,7 +26,9 @@ impl From<&PgRow> for Recipient {
 
 pub struct Message {
     pub id: i32,
+    pub group_id: Option<i32>,
     pub message: Vec<u8>,
+    pub expires: NaiveDateTime,
     pub created: NaiveDateTime,
 }
 
@@ -34,7 +36,9 @@ impl From<&PgRow> for Message {
     fn from(row: &PgRow) -> Self {
         Message {
             id: row.get("id"),
+            group_id: row.get("group_id"),
             message: row.get("message"),
+            expires: row.get("expires"),
             created: row.get("created"),
         }
     }
@@ -42,12 +46,16 @@ impl From<&PgRow> for Message {
 
 impl Message {
     pub async fn create(&mut self, db: &DbPool, client_ids: &[i32]) -> Result<(), sqlx::Error> {
-        *self = sqlx::query("INSERT INTO message (message) VALUES ($1) RETURNING *;")
-            .bind(&self.message)
-            .fetch_one(db)
-            .await?
-            .borrow()
-            .into();
+        *self = sqlx::query(
+            "INSERT INTO message (group_id, message, expires) VALUES ($1, $2, $3) RETURNING *;",
+        )
+        .bind(self.group_id)
+        .bind(&self.message)
+        .bind(self.expires)
+        .fetch_one(db)
+        .await?
+        .borrow()
+        .into();
 
         sqlx::query("INSERT INTO recipient (client_id, message_id) SELECT * FROM UNNEST($1::int8[], $2::int8[]);")
             .bind(client_ids)
@@ -105,18 +113,50 @@ impl Message {
         Ok(())
     }
 
-    pub async fn from_client_id(db: &DbPool, client_id: i32) -> Result<Vec<Message>, sqlx::Error> {
+    // returns up to `limit` messages with an id greater than `after`, oldest first
+    pub async fn from_client_id(
+        db: &DbPool,
+        client_id: i32,
+        after: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<Message>, sqlx::Error> {
         Ok(sqlx::query(
             "SELECT message.*
         FROM message
         INNER JOIN recipient ON message.id = recipient.message_id
-        WHERE recipient.client_id = $1 ORDER BY message.created ASC;",
+        WHERE recipient.client_id = $1 AND message.id > $2 ORDER BY message.id ASC LIMIT $3;",
         )
         .bind(client_id)
+        .bind(after.unwrap_or(0))
+        .bind(limit)
         .fetch_all(db)
         .await?
         .iter()
         .map(|row| row.into())
         .collect())
     }
+
+    // deletes every message that expired before `now`, recording for each recipient how many messages of each group it lost
+    pub async fn purge_expired(db: &DbPool, now: NaiveDateTime) -> Result<u64, sqlx::Error> {
+        let mut tx = db.begin().await?;
+        sqlx::query(
+            "INSERT INTO expired_message (client_id, group_id, amount)
+        SELECT recipient.client_id, message.group_id, COUNT(*)
+        FROM recipient
+        INNER JOIN message ON message.id = recipient.message_id
+        WHERE message.expires < $1
+        GROUP BY recipient.client_id, message.group_id;",
+        )
+        .bind(now)
+        .execute(&mut tx)
+        .await?;
+
+        let deleted = sqlx::query("DELETE FROM message WHERE expires < $1;")
+            .bind(now)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted)
+    }
 }
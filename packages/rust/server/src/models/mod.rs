@@ -1,7 +1,13 @@
+pub mod blob;
 pub mod client;
 pub mod confirmation;
+pub mod device_link;
+pub mod expired_message;
 pub mod forgot;
+pub mod group;
+pub mod group_commit;
 pub mod key_package;
 pub mod message;
+pub mod push_token;
 pub mod session;
 pub mod user;
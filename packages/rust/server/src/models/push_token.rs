@@ -0,0 +1,67 @@
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::Row;
+use std::borrow::Borrow;
+
+use crate::types::DbPool;
+
+// the token a client registered to be woken up by push notifications, at most one exists per client
+pub struct PushToken {
+    pub id: i32,
+    pub client_id: i32,
+    pub token: String,
+    pub created: NaiveDateTime,
+}
+
+impl From<&PgRow> for PushToken {
+    fn from(row: &PgRow) -> Self {
+        PushToken {
+            id: row.get("id"),
+            client_id: row.get("client_id"),
+            token: row.get("token"),
+            created: row.get("created"),
+        }
+    }
+}
+
+impl PushToken {
+    // replaces the client's current token
+    pub async fn create(&mut self, db: &DbPool) -> Result<(), sqlx::Error> {
+        *self = sqlx::query(
+            "INSERT INTO push_token (client_id, token) VALUES ($1, $2)
+        ON CONFLICT (client_id) DO UPDATE SET token = $2, created = CURRENT_TIMESTAMP RETURNING *;",
+        )
+        .bind(self.client_id)
+        .bind(&self.token)
+        .fetch_one(db)
+        .await?
+        .borrow()
+        .into();
+
+        Ok(())
+    }
+
+    pub async fn from_client_ids(
+        db: &DbPool,
+        client_ids: &[i32],
+    ) -> Result<Vec<PushToken>, sqlx::Error> {
+        // a bug of the parameter typechecking code requires all array parameters to be slices
+        Ok(
+            sqlx::query("SELECT * FROM push_token WHERE client_id = ANY($1);")
+                .bind(client_ids)
+                .fetch_all(db)
+                .await?
+                .iter()
+                .map(|row| row.into())
+                .collect(),
+        )
+    }
+
+    pub async fn delete_by_client_id(db: &DbPool, client_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM push_token WHERE client_id = $1;")
+            .bind(client_id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,17 +1,26 @@
 use crate::routes;
 
 use crate::config::CONFIG;
-use crate::types::{DbPool, EmailServiceArc};
+use crate::services::broker::MessageBroker;
+use crate::types::{DbPool, EmailServiceArc, MessageBrokerArc, PushServiceArc};
 use axum::http::StatusCode;
 use axum::routing::get;
 use axum::{Extension, Router};
 use sqlx::Row;
+use std::sync::Arc;
 
-pub fn router(pool: DbPool, email_service: EmailServiceArc) -> Router {
+pub fn router(
+    pool: DbPool,
+    email_service: EmailServiceArc,
+    push_service: PushServiceArc,
+) -> Router {
     let v1 = Router::new()
         .nest("/user", routes::user::router())
         .nest("/client", routes::client::router())
-        .nest("/message", routes::message::router());
+        .nest("/message", routes::message::router())
+        .nest("/group", routes::group::router())
+        .nest("/blob", routes::blob::router())
+        .nest("/device_link", routes::device_link::router());
 
     Router::new()
         .route("/", get(status))
@@ -19,6 +28,10 @@ pub fn router(pool: DbPool, email_service: EmailServiceArc) -> Router {
         .nest("/v1", v1)
         .layer(Extension(pool))
         .layer(Extension(email_service))
+        .layer(Extension(push_service))
+        .layer(Extension::<MessageBrokerArc>(Arc::new(
+            MessageBroker::default(),
+        )))
 }
 
 async fn status() -> (StatusCode, String) {
@@ -41,9 +54,17 @@ async fn reset(db: Extension<DbPool>) -> StatusCode {
     }
 
     let query: Vec<&str> = r#"
+DELETE FROM "device_link";
+DELETE FROM "blob_recipient";
+DELETE FROM "blob";
 DELETE FROM "key_package";
+DELETE FROM "push_token";
 DELETE FROM "recipient";
+DELETE FROM "expired_message";
 DELETE FROM "message";
+DELETE FROM "group_commit";
+DELETE FROM "group_member";
+DELETE FROM "group";
 DELETE FROM "forgot";
 DELETE FROM "confirmation";
 DELETE FROM "session";
@@ -0,0 +1,90 @@
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::Router;
+use axum::{Extension, Json};
+use sha2::{Digest, Sha256};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+
+use crate::config::CONFIG;
+use crate::extractor::authenticated_user::AuthenticatedUser;
+use crate::models::blob::Blob;
+use crate::models::client::Client;
+use crate::routes::map_sqlx_err;
+use crate::types::DbPool;
+use common::base64::{deserialize_url, Base64};
+use common::http_types::{Blob as BlobBody, DownloadBlob, UploadBlob, UploadBlobResponse};
+
+const MAX_BLOB_SIZE: usize = 5 * 1024 * 1024;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", post(upload))
+        .route("/:hash", get(download))
+}
+
+async fn upload(
+    db: Extension<DbPool>,
+    Json(mut payload): Json<UploadBlob>,
+    user: AuthenticatedUser,
+) -> Result<(StatusCode, Json<UploadBlobResponse>), StatusCode> {
+    if payload.data.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if payload.data.len() > MAX_BLOB_SIZE {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    payload.client_uuids.sort_unstable();
+    payload.client_uuids.dedup();
+    let clients = Client::filter_uuids(&db, &payload.client_uuids)
+        .await
+        .map_err(map_sqlx_err)?;
+    if clients.len() != payload.client_uuids.len() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let client_ids: Vec<_> = clients.iter().map(|client| client.id).collect();
+
+    let mut blob = Blob {
+        id: 0,
+        hash: Sha256::digest(&payload.data.0).to_vec(),
+        data: payload.data.0,
+        user_id: user.id,
+        expires: NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + CONFIG.blob_ttl, 0)
+            .unwrap(), // unwrap is safe because the config clamps the ttl
+        created: NaiveDateTime::default(),
+    };
+    blob.create(&db, &client_ids).await.map_err(map_sqlx_err)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UploadBlobResponse {
+            hash: Base64(blob.hash),
+        }),
+    ))
+}
+
+// blobs are encrypted, any user that knows the hash may download them
+async fn download(
+    db: Extension<DbPool>,
+    Path(hash): Path<String>,
+    Query(query): Query<DownloadBlob>,
+    user: AuthenticatedUser,
+) -> Result<Json<BlobBody>, StatusCode> {
+    let hash = deserialize_url(&hash).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let blob = Blob::from_hash(&db, &hash).await.map_err(map_sqlx_err)?;
+
+    if let Some(client_uuid) = query.client_uuid {
+        let client = Client::from_uuid(&db, &client_uuid)
+            .await
+            .map_err(map_sqlx_err)?;
+        if client.user_id != user.id {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        blob.fetched(&db, client.id).await.map_err(map_sqlx_err)?;
+    }
+
+    Ok(Json(BlobBody {
+        data: Base64(blob.data),
+    }))
+}
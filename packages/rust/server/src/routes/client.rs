@@ -1,6 +1,6 @@
 use axum::extract::Path;
 use axum::http::StatusCode;
-use axum::routing::{get, post};
+use axum::routing::{get, post, put};
 use axum::Router;
 use axum::{Extension, Json};
 use ed25519_dalek::{PublicKey, Signature, Verifier};
@@ -10,13 +10,14 @@ use sqlx::types::Uuid;
 use crate::extractor::authenticated_user::AuthenticatedUser;
 use crate::models::client::Client;
 use crate::models::key_package::KeyPackage as KeyPackageModel;
+use crate::models::push_token::PushToken;
 use crate::models::user::User;
 use crate::routes::map_sqlx_err;
 use crate::types::DbPool;
 use common::base64::Base64;
 use common::http_types::{
-    CreateClient, CreateClientResponse, KeyPackagePublic, PublicClient, ReplaceKeyPackages,
-    UpdateClient,
+    CreateClient, CreateClientResponse, KeyPackagePublic, PublicClient, RegisterPushToken,
+    ReplaceKeyPackages, UpdateClient,
 };
 use openmls::key_packages::KeyPackageIn;
 use openmls::prelude::TlsDeserializeTrait;
@@ -30,6 +31,10 @@ pub fn router() -> Router {
         )
         .route("/:uuid/key_packages", post(replace_key_packages))
         .route("/:uuid/key_package", get(get_key_package))
+        .route(
+            "/:uuid/push_token",
+            put(register_push_token).delete(revoke_push_token),
+        )
 }
 
 pub async fn create(
@@ -68,11 +73,10 @@ pub async fn create(
 
 pub async fn update(
     db: Extension<DbPool>,
-    Path(uuid): Path<String>,
+    Path(uuid): Path<Uuid>,
     Json(payload): Json<UpdateClient>,
     user: AuthenticatedUser,
 ) -> Result<StatusCode, StatusCode> {
-    let uuid = Uuid::parse_str(&uuid).map_err(|_| StatusCode::BAD_REQUEST)?;
     let mut client = Client::from_uuid(&db, &uuid).await.map_err(map_sqlx_err)?;
     if client.user_id != user.id {
         return Err(StatusCode::FORBIDDEN);
@@ -114,16 +118,18 @@ pub async fn get_client(
 
 pub async fn delete_client(
     db: Extension<DbPool>,
-    Path(uuid): Path<String>,
+    Path(uuid): Path<Uuid>,
     user: AuthenticatedUser,
 ) -> Result<StatusCode, StatusCode> {
-    let uuid = Uuid::parse_str(&uuid).map_err(|_| StatusCode::BAD_REQUEST)?;
     let client = Client::from_uuid(&db, &uuid).await.map_err(map_sqlx_err)?;
 
     if client.user_id != user.id {
         return Err(StatusCode::FORBIDDEN);
     }
 
+    KeyPackageModel::delete_all_by_client_id(&db, client.id)
+        .await
+        .map_err(map_sqlx_err)?;
     client.delete(&db).await.map_err(map_sqlx_err)?;
 
     Ok(StatusCode::OK)
@@ -194,3 +200,49 @@ pub async fn get_key_package(
         key_package: Base64(key_package.key_package),
     }))
 }
+
+pub async fn register_push_token(
+    db: Extension<DbPool>,
+    Path(uuid): Path<Uuid>,
+    Json(payload): Json<RegisterPushToken>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    let client = Client::from_uuid(&db, &uuid).await.map_err(map_sqlx_err)?;
+
+    if client.user_id != user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if payload.token.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    PushToken {
+        id: 0,
+        client_id: client.id,
+        token: payload.token,
+        created: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(), // unwrap is safe because timestamp is 0
+    }
+    .create(&db)
+    .await
+    .map_err(map_sqlx_err)?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn revoke_push_token(
+    db: Extension<DbPool>,
+    Path(uuid): Path<Uuid>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    let client = Client::from_uuid(&db, &uuid).await.map_err(map_sqlx_err)?;
+
+    if client.user_id != user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    PushToken::delete_by_client_id(&db, client.id)
+        .await
+        .map_err(map_sqlx_err)?;
+
+    Ok(StatusCode::OK)
+}
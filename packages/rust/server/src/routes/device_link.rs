@@ -0,0 +1,132 @@
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::Router;
+use axum::{Extension, Json};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+
+use crate::extractor::authenticated_user::AuthenticatedUser;
+use crate::models::client::Client;
+use crate::models::device_link::DeviceLink;
+use crate::routes::map_sqlx_err;
+use crate::types::DbPool;
+use common::base64::Base64;
+use common::device_link;
+use common::http_types::{
+    CreateDeviceLink, CreateDeviceLinkResponse, DeviceLink as DeviceLinkBody, UpdateDeviceLink,
+};
+
+// seconds
+const DEVICE_LINK_TTL: i64 = 10 * 60;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", post(create))
+        .route("/:code", get(get_link).patch(update).delete(delete_link))
+}
+
+// links are only visible to the user that created them, so the code only has to be unique per user
+// the code is derived from the public key, the device entering it checks the key with it
+async fn create(
+    db: Extension<DbPool>,
+    Json(payload): Json<CreateDeviceLink>,
+    user: AuthenticatedUser,
+) -> Result<(StatusCode, Json<CreateDeviceLinkResponse>), StatusCode> {
+    if payload.public_key.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let code = device_link::code(&payload.public_key);
+    match DeviceLink::from_code(&db, user.id, &code, Utc::now().naive_utc()).await {
+        Ok(_) => return Err(StatusCode::CONFLICT),
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(err) => return Err(map_sqlx_err(err)),
+    }
+    let mut link = DeviceLink {
+        id: 0,
+        user_id: user.id,
+        code,
+        public_key: payload.public_key.0,
+        encrypted_key: None,
+        client_id: None,
+        expires: NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + DEVICE_LINK_TTL, 0)
+            .unwrap(),
+        created: NaiveDateTime::default(),
+    };
+    link.create(&db).await.map_err(map_sqlx_err)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateDeviceLinkResponse { code: link.code }),
+    ))
+}
+
+async fn get_link(
+    db: Extension<DbPool>,
+    Path(code): Path<String>,
+    user: AuthenticatedUser,
+) -> Result<Json<DeviceLinkBody>, StatusCode> {
+    let link = DeviceLink::from_code(&db, user.id, &code, Utc::now().naive_utc())
+        .await
+        .map_err(map_sqlx_err)?;
+    let client_uuid = match link.client_id {
+        Some(client_id) => Some(
+            Client::from_id(&db, client_id)
+                .await
+                .map_err(map_sqlx_err)?
+                .uuid,
+        ),
+        None => None,
+    };
+
+    Ok(Json(DeviceLinkBody {
+        public_key: Base64(link.public_key),
+        encrypted_key: link.encrypted_key.map(Base64),
+        client_uuid,
+    }))
+}
+
+// each field can only be set once
+async fn update(
+    db: Extension<DbPool>,
+    Path(code): Path<String>,
+    Json(payload): Json<UpdateDeviceLink>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    let mut link = DeviceLink::from_code(&db, user.id, &code, Utc::now().naive_utc())
+        .await
+        .map_err(map_sqlx_err)?;
+
+    if let Some(encrypted_key) = payload.encrypted_key {
+        if link.encrypted_key.is_some() {
+            return Err(StatusCode::CONFLICT);
+        }
+        link.encrypted_key = Some(encrypted_key.0);
+    }
+    if let Some(client_uuid) = payload.client_uuid {
+        if link.client_id.is_some() {
+            return Err(StatusCode::CONFLICT);
+        }
+        let client = Client::from_uuid(&db, &client_uuid)
+            .await
+            .map_err(map_sqlx_err)?;
+        if client.user_id != user.id {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        link.client_id = Some(client.id);
+    }
+    link.update(&db).await.map_err(map_sqlx_err)?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn delete_link(
+    db: Extension<DbPool>,
+    Path(code): Path<String>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    let link = DeviceLink::from_code(&db, user.id, &code, Utc::now().naive_utc())
+        .await
+        .map_err(map_sqlx_err)?;
+    link.delete(&db).await.map_err(map_sqlx_err)?;
+    Ok(StatusCode::OK)
+}
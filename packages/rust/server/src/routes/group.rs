@@ -0,0 +1,178 @@
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use axum::{Extension, Json};
+use openmls::prelude::{
+    ContentType, MlsMessageIn, MlsMessageInBody, ProtocolMessage, TlsDeserializeTrait,
+};
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::types::Uuid;
+
+use crate::extractor::authenticated_user::AuthenticatedUser;
+use crate::models::client::Client;
+use crate::models::group::Group;
+use crate::models::group_commit::GroupCommit;
+use crate::routes::map_sqlx_err;
+use crate::routes::message::deliver_message;
+use crate::types::{DbPool, MessageBrokerArc, PushServiceArc};
+use common::base64::Base64;
+use common::http_types::{CommitConflict, CreateGroup, SendGroupMessage};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/:uuid", post(create))
+        .route("/:uuid/message", post(send_message))
+}
+
+async fn create(
+    db: Extension<DbPool>,
+    Path(uuid): Path<Uuid>,
+    Json(payload): Json<CreateGroup>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    let client = Client::from_uuid(&db, &payload.client_uuid)
+        .await
+        .map_err(map_sqlx_err)?;
+    if client.user_id != user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    match Group::from_uuid(&db, &uuid).await {
+        Ok(_) => return Err(StatusCode::CONFLICT),
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(err) => return Err(map_sqlx_err(err)),
+    }
+
+    let mut group = Group {
+        id: 0,
+        uuid,
+        created: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(), // unwrap is safe because timestamp is 0
+    };
+    group.create(&db).await.map_err(map_sqlx_err)?;
+    // the members of a group that existed before the server knew about groups, clients that were deleted are skipped
+    let mut client_ids = vec![client.id];
+    client_ids.extend(
+        Client::filter_uuids(&db, &payload.members)
+            .await
+            .map_err(map_sqlx_err)?
+            .iter()
+            .map(|member| member.id)
+            .filter(|id| *id != client.id),
+    );
+    group
+        .set_members(&db, &client_ids)
+        .await
+        .map_err(map_sqlx_err)?;
+
+    Ok(StatusCode::CREATED)
+}
+
+async fn send_message(
+    db: Extension<DbPool>,
+    broker: Extension<MessageBrokerArc>,
+    push_service: Extension<PushServiceArc>,
+    Path(uuid): Path<Uuid>,
+    Json(payload): Json<SendGroupMessage>,
+    user: AuthenticatedUser,
+) -> Result<Response, StatusCode> {
+    let group = Group::from_uuid(&db, &uuid).await.map_err(map_sqlx_err)?;
+
+    let sender = Client::from_uuid(&db, &payload.client_uuid)
+        .await
+        .map_err(map_sqlx_err)?;
+    if sender.user_id != user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let members = group.members(&db).await.map_err(map_sqlx_err)?;
+    if !members.iter().any(|client| client.id == sender.id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let epoch = commit_epoch(&payload.message.message.0);
+    let new_members = match payload.members {
+        // the members only change with a commit
+        Some(_) if epoch.is_none() => return Err(StatusCode::BAD_REQUEST),
+        Some(mut client_uuids) => {
+            client_uuids.sort_unstable();
+            client_uuids.dedup();
+            let clients = Client::filter_uuids(&db, &client_uuids)
+                .await
+                .map_err(map_sqlx_err)?;
+            if clients.len() != client_uuids.len() {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            Some(clients.iter().map(|client| client.id).collect::<Vec<_>>())
+        }
+        None => None,
+    };
+
+    // only one commit is accepted per epoch, everyone else has to process the winner and commit again
+    if let Some(epoch) = epoch {
+        let mut commit = GroupCommit {
+            id: 0,
+            group_id: group.id,
+            epoch: epoch as i64,
+            commit: payload.message.message.0.clone(),
+            created: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(), // unwrap is safe because timestamp is 0
+        };
+        if !commit.create(&db).await.map_err(map_sqlx_err)? {
+            let winner = GroupCommit::from_epoch(&db, group.id, epoch as i64)
+                .await
+                .map_err(map_sqlx_err)?;
+            // a retry of the accepted commit is delivered again, members skip epochs they already processed
+            if winner.commit != commit.commit {
+                return Ok((
+                    StatusCode::CONFLICT,
+                    Json(CommitConflict {
+                        commit: Base64(winner.commit),
+                    }),
+                )
+                    .into_response());
+            }
+        }
+        // the commit itself still goes to the members it was sent in
+        if let Some(new_members) = new_members {
+            group
+                .set_members(&db, &new_members)
+                .await
+                .map_err(map_sqlx_err)?;
+        }
+    }
+
+    let client_ids: Vec<_> = members
+        .iter()
+        .filter(|client| !payload.exclude_client_uuids.contains(&client.uuid))
+        .map(|client| client.id)
+        .collect();
+    if client_ids.is_empty() {
+        return Ok(StatusCode::OK.into_response());
+    }
+
+    deliver_message(
+        &db,
+        &broker,
+        &push_service,
+        &client_ids,
+        payload.message.message.0,
+        Some(&group),
+    )
+    .await?;
+
+    Ok(StatusCode::OK.into_response())
+}
+
+// the epoch of a commit, None for any other message
+// proposals don't advance the epoch so any number of them can be sent for one
+fn commit_epoch(message: &[u8]) -> Option<u64> {
+    let message: ProtocolMessage =
+        match MlsMessageIn::tls_deserialize_exact(message).ok()?.extract() {
+            MlsMessageInBody::PublicMessage(message) => message.into(),
+            MlsMessageInBody::PrivateMessage(message) => message.into(),
+            _ => return None,
+        };
+    if message.content_type() != ContentType::Commit {
+        return None;
+    }
+    Some(message.epoch().as_u64())
+}
@@ -1,23 +1,43 @@
+use crate::config::CONFIG;
 use crate::extractor::authenticated_user::AuthenticatedUser;
 use crate::models::client::Client;
+use crate::models::expired_message::ExpiredMessage;
+use crate::models::group::Group;
 use crate::models::message::Message;
+use crate::models::push_token::PushToken;
 
-use crate::types::DbPool;
+use crate::routes::map_sqlx_err;
+use crate::types::{DbPool, MessageBrokerArc, PushServiceArc};
 use axum::http::StatusCode;
-use axum::routing::get;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
 use axum::Router;
 use axum::{Extension, Json};
 use common::base64::Base64;
-use common::http_types::{CheckMessages, DeliveredMessage, MessagesResponse, SendMessage};
-use sqlx::types::chrono::NaiveDateTime;
+use common::http_types::{
+    AckMessages, CheckMessages, DeliveredMessage, ExpiredMessages, MessagesResponse, SendMessage,
+};
+use futures_util::stream::{self, Stream};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::iter::Iterator;
+use tokio::sync::broadcast::error::RecvError;
+
+const DEFAULT_MESSAGE_LIMIT: u32 = 100;
+const MAX_MESSAGE_LIMIT: u32 = 500;
 
 pub fn router() -> Router {
-    Router::new().route("/", get(receive_message).post(send_message))
+    Router::new()
+        .route("/", get(receive_message).post(send_message))
+        .route("/stream", get(stream_messages))
+        .route("/ack", post(ack_messages))
 }
 
 async fn send_message(
     db: Extension<DbPool>,
+    broker: Extension<MessageBrokerArc>,
+    push_service: Extension<PushServiceArc>,
     Json(payload): Json<SendMessage>,
     _: AuthenticatedUser,
 ) -> Result<StatusCode, StatusCode> {
@@ -34,17 +54,106 @@ async fn send_message(
     }
     let client_ids: Vec<_> = clients.iter().map(|client| client.id).collect();
 
+    let group = match payload.group_uuid {
+        Some(group_uuid) => Some(
+            Group::from_uuid(&db, &group_uuid)
+                .await
+                .map_err(map_sqlx_err)?,
+        ),
+        None => None,
+    };
+
+    deliver_message(
+        &db,
+        &broker,
+        &push_service,
+        &client_ids,
+        payload.message.message.0,
+        group.as_ref(),
+    )
+    .await?;
+
+    Ok(StatusCode::OK)
+}
+
+// stores the message for every recipient, pushes it to their open message streams and wakes up the ones with a push token
+pub async fn deliver_message(
+    db: &DbPool,
+    broker: &MessageBrokerArc,
+    push_service: &PushServiceArc,
+    client_ids: &[i32],
+    message: Vec<u8>,
+    group: Option<&Group>,
+) -> Result<(), StatusCode> {
     let mut message = Message {
         id: Default::default(),
-        message: payload.message.message.0,
+        group_id: group.map(|group| group.id),
+        message,
+        expires: NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + CONFIG.message_ttl, 0)
+            .unwrap(), // unwrap is safe because the config clamps the ttl
         created: NaiveDateTime::from_timestamp_opt(0, 0).unwrap(), // unwrap is safe because timestamp is 0
     };
     message
-        .create(&db, &client_ids)
+        .create(db, client_ids)
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
+    broker.publish(
+        client_ids,
+        DeliveredMessage {
+            id: message.id,
+            group_uuid: group.map(|group| group.uuid),
+            message: Base64(message.message),
+            received_date: message.created.timestamp_millis(),
+        },
+    );
 
-    Ok(StatusCode::OK)
+    // the message is already stored, a client we fail to wake up gets it the next time it fetches
+    let tokens: Vec<_> = match PushToken::from_client_ids(db, client_ids).await {
+        Ok(push_tokens) => push_tokens
+            .into_iter()
+            .map(|push_token| push_token.token)
+            .collect(),
+        Err(err) => {
+            eprintln!("Unable to look up push tokens: {}", err);
+            return Ok(());
+        }
+    };
+    if !tokens.is_empty() {
+        if let Err(err) = push_service.send(&tokens) {
+            eprintln!("Unable to send push notifications: {}", err);
+        }
+    }
+    Ok(())
+}
+
+async fn stream_messages(
+    db: Extension<DbPool>,
+    broker: Extension<MessageBrokerArc>,
+    Json(payload): Json<CheckMessages>,
+    user: AuthenticatedUser,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let client = Client::from_uuid(&db, &payload.client_uuid)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if client.user_id != user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let receiver = broker.subscribe(client.id);
+    let stream = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            let message = match receiver.recv().await {
+                Ok(message) => message,
+                // messages we fell behind on are still queued and will be returned by GET /message
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            };
+            let event = Event::default().json_data(&*message).unwrap(); // unwrap is safe because DeliveredMessage always serializes
+            return Some((Ok(event), receiver));
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
 async fn receive_message(
@@ -61,25 +170,55 @@ async fn receive_message(
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let messages = Message::from_client_id(&db, client.id)
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_MESSAGE_LIMIT)
+        .clamp(1, MAX_MESSAGE_LIMIT);
+
+    // messages stay queued until the client acknowledges them
+    // fetch one extra message to know whether there is another page
+    let mut messages = Message::from_client_id(&db, client.id, payload.cursor, limit as i64 + 1)
         .await
         .map_err(|_| StatusCode::NOT_FOUND)?;
-    if messages.is_empty() {
-        return Ok((
-            StatusCode::OK,
-            Json(MessagesResponse {
-                messages: Vec::new(),
-            }),
-        ));
-    }
-    let ids: Vec<_> = messages.iter().map(|message| message.id).collect();
-    Message::delete_ids(&ids, client.id, &db)
+    let has_more = messages.len() > limit as usize;
+    messages.truncate(limit as usize);
+    let next_cursor = messages.last().map(|message| message.id);
+
+    let mut group_ids: Vec<_> = messages
+        .iter()
+        .filter_map(|message| message.group_id)
+        .collect();
+    group_ids.sort_unstable();
+    group_ids.dedup();
+    let group_uuids: HashMap<_, _> = Group::from_ids(&db, &group_ids)
         .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+        .map_err(map_sqlx_err)?
+        .into_iter()
+        .map(|group| (group.id, group.uuid))
+        .collect();
+
+    let expired = if payload.cursor.is_none() {
+        ExpiredMessage::from_client_id(&db, client.id)
+            .await
+            .map_err(map_sqlx_err)?
+            .into_iter()
+            .map(|expired| ExpiredMessages {
+                id: expired.id,
+                group_uuid: expired.group_uuid,
+                amount: expired.amount,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
 
     let messages_to_return = messages
         .into_iter()
         .map(|message| DeliveredMessage {
+            id: message.id,
+            group_uuid: message
+                .group_id
+                .and_then(|group_id| group_uuids.get(&group_id).copied()),
             message: Base64(message.message),
             received_date: message.created.timestamp_millis(),
         })
@@ -89,6 +228,34 @@ async fn receive_message(
         StatusCode::OK,
         Json(MessagesResponse {
             messages: messages_to_return,
+            has_more,
+            next_cursor,
+            expired,
         }),
     ))
 }
+
+async fn ack_messages(
+    db: Extension<DbPool>,
+    Json(payload): Json<AckMessages>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, StatusCode> {
+    let client = Client::from_uuid(&db, &payload.client_uuid)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    if client.user_id != user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if !payload.message_ids.is_empty() {
+        Message::delete_ids(&payload.message_ids, client.id, &db)
+            .await
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+    }
+    if !payload.expired_ids.is_empty() {
+        ExpiredMessage::delete_ids(&payload.expired_ids, client.id, &db)
+            .await
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+    }
+
+    Ok(StatusCode::OK)
+}
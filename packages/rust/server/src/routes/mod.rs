@@ -1,6 +1,9 @@
 use axum::http::StatusCode;
 
+pub mod blob;
 pub mod client;
+pub mod device_link;
+pub mod group;
 pub mod message;
 pub mod user;
 
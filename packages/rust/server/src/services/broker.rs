@@ -0,0 +1,36 @@
+use common::http_types::DeliveredMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+// how many messages a slow stream may fall behind before it starts skipping, skipped messages stay queued in the db
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Hands newly created messages to every open message stream of their recipient clients.
+#[derive(Default)]
+pub struct MessageBroker {
+    channels: Mutex<HashMap<i32, broadcast::Sender<Arc<DeliveredMessage>>>>,
+}
+
+impl MessageBroker {
+    pub fn subscribe(&self, client_id: i32) -> broadcast::Receiver<Arc<DeliveredMessage>> {
+        let mut channels = self.channels.lock().unwrap();
+        channels
+            .entry(client_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    pub fn publish(&self, client_ids: &[i32], message: DeliveredMessage) {
+        let message = Arc::new(message);
+        let mut channels = self.channels.lock().unwrap();
+        for client_id in client_ids {
+            if let Some(sender) = channels.get(client_id) {
+                if sender.send(message.clone()).is_err() {
+                    // every stream for this client has been closed
+                    channels.remove(client_id);
+                }
+            }
+        }
+    }
+}
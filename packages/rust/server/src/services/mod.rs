@@ -1,3 +1,5 @@
+pub mod broker;
 pub mod email;
 pub mod password;
+pub mod push;
 pub mod session;
@@ -0,0 +1,18 @@
+use std::error::Error;
+
+#[derive(Default, Clone, Copy)]
+pub struct PrinterPushService {}
+
+pub type PushError = Box<dyn Error + Send + Sync>;
+
+// wakes clients up so they fetch their messages, the notification itself carries no content
+pub trait PushService {
+    fn send(&self, tokens: &[String]) -> Result<(), PushError>;
+}
+
+impl PushService for PrinterPushService {
+    fn send(&self, tokens: &[String]) -> Result<(), PushError> {
+        println!("Mock Push sent to: {:?}", tokens);
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
+use crate::services::broker::MessageBroker;
 use crate::services::email::EmailService;
+use crate::services::push::PushService;
 
 use openmls::prelude::{Ciphersuite, SignatureScheme};
 
@@ -14,3 +16,7 @@ pub const CIPHERSUITES: Ciphersuite =
 pub type DbPool = Pool<Postgres>;
 
 pub type EmailServiceArc = Arc<(dyn EmailService + Send + Sync)>;
+
+pub type PushServiceArc = Arc<dyn PushService + Send + Sync>;
+
+pub type MessageBrokerArc = Arc<MessageBroker>;
@@ -0,0 +1,212 @@
+use crate::crypto_helper::{PRIVATE, PUBLIC};
+use crate::helper::{start_server, TempDatabase};
+use axum::http::StatusCode;
+use axum_test_helper::TestClient;
+use common::base64::{serialize_url, Base64};
+use common::http_types::{Blob as BlobBody, CreateUser, UploadBlob, UploadBlobResponse};
+use server::config::CONFIG;
+use server::models::blob::Blob;
+use sha2::{Digest, Sha256};
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use uuid::Uuid;
+
+mod crypto_helper;
+mod helper;
+
+async fn create_user_with_client(
+    db: &TempDatabase,
+    client: &TestClient,
+    name: &str,
+) -> (String, Uuid) {
+    let created_user = CreateUser {
+        email: format!("{}@gmail.com", name),
+        username: format!("{}_username", name),
+        password: "test_password".to_string(),
+        name: name.to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), client, &created_user)
+        .await
+        .unwrap();
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, client).await;
+    (bearer, client_uuid)
+}
+
+async fn download(
+    client: &TestClient,
+    bearer: &str,
+    data: &[u8],
+    client_uuid: Option<Uuid>,
+) -> StatusCode {
+    let query = match client_uuid {
+        Some(client_uuid) => format!("?client_uuid={}", client_uuid),
+        None => String::new(),
+    };
+    client
+        .get(&format!("/v1/blob/{}{}", serialize_url(&hash(data)), query))
+        .header("Authorization", bearer)
+        .send()
+        .await
+        .status()
+}
+
+#[tokio::test]
+async fn test_blob() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (bearer, _) = create_user_with_client(&db, &client, "alice").await;
+    let (bob_bearer, _) = create_user_with_client(&db, &client, "bob").await;
+
+    let data = vec![1, 2, 3, 4];
+    let upload = UploadBlob {
+        data: Base64(data.clone()),
+        client_uuids: Vec::new(),
+    };
+
+    // only users can upload
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", format!("Bearer {}", Uuid::new_v4()))
+        .json(&upload)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", bearer.clone())
+        .json(&upload)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let res: UploadBlobResponse = res.json().await;
+    assert_eq!(res.hash, hash(&data));
+
+    // uploading it again returns the same hash
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", bearer.clone())
+        .json(&upload)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let again: UploadBlobResponse = res.json().await;
+    assert_eq!(again.hash, hash(&data));
+
+    // anyone that knows the hash can download it
+    let res = client
+        .get(&format!("/v1/blob/{}", serialize_url(&hash(&data))))
+        .header("Authorization", bob_bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let blob: BlobBody = res.json().await;
+    assert_eq!(blob.data.0, data);
+
+    let status = download(&client, &bearer, &[5], None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", bearer.clone())
+        .json(&UploadBlob {
+            data: Base64(vec![0; 5 * 1024 * 1024 + 1]),
+            client_uuids: Vec::new(),
+        })
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_blob_recipients() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (bearer, _) = create_user_with_client(&db, &client, "alice").await;
+    let (bob_bearer, bob_client) = create_user_with_client(&db, &client, "bob").await;
+    let (charlie_bearer, charlie_client) = create_user_with_client(&db, &client, "charlie").await;
+
+    let data = vec![1, 2, 3, 4];
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", bearer.clone())
+        .json(&UploadBlob {
+            data: Base64(data.clone()),
+            client_uuids: vec![bob_client, charlie_client],
+        })
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // only the owner of a client can download as it
+    let status = download(&client, &bearer, &data, Some(bob_client)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = download(&client, &bob_bearer, &data, Some(bob_client)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // charlie didn't download it yet
+    let status = download(&client, &bob_bearer, &data, Some(bob_client)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let status = download(&client, &charlie_bearer, &data, Some(charlie_client)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let status = download(&client, &bob_bearer, &data, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", bearer.clone())
+        .json(&UploadBlob {
+            data: Base64(data.clone()),
+            client_uuids: vec![Uuid::new_v4()],
+        })
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_blob_expiry() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (bearer, _) = create_user_with_client(&db, &client, "alice").await;
+    let (_, bob_client) = create_user_with_client(&db, &client, "bob").await;
+
+    let data = vec![1, 2, 3, 4];
+    let res = client
+        .post("/v1/blob")
+        .header("Authorization", bearer.clone())
+        .json(&UploadBlob {
+            data: Base64(data.clone()),
+            client_uuids: vec![bob_client],
+        })
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let purged = Blob::purge_expired(db.pool(), Utc::now().naive_utc())
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+
+    let purged = Blob::purge_expired(
+        db.pool(),
+        NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + CONFIG.blob_ttl + 1, 0).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(purged, 1);
+
+    let status = download(&client, &bearer, &data, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+fn hash(data: &[u8]) -> Base64 {
+    Base64(Sha256::digest(data).to_vec())
+}
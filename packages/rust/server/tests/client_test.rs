@@ -1,4 +1,4 @@
-use crate::helper::{create_client, start_server, TempDatabase};
+use crate::helper::{create_client, start_server, start_server_with_push, TempDatabase};
 use axum::http::StatusCode;
 use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
 use openmls::prelude::*;
@@ -11,10 +11,12 @@ use uuid::Uuid;
 use crate::crypto_helper::{PRIVATE, PUBLIC};
 use common::base64::Base64;
 use common::http_types::{
-    ClientsResponse, CreateClient, CreateClientResponse, CreateUser, KeyPackagePublic,
-    PublicClient, ReplaceKeyPackages,
+    ClientsResponse, CreateClient, CreateClientResponse, CreateUser, KeyPackagePublic, Message,
+    PublicClient, RegisterPushToken, ReplaceKeyPackages, SendMessage,
 };
+use server::services::push::{PushError, PushService};
 use server::types::{CIPHERSUITES, SIGNATURE_SCHEME};
+use std::sync::{Arc, Mutex};
 
 mod crypto_helper;
 mod helper;
@@ -275,6 +277,24 @@ async fn test_key_packages() {
         .get("count");
 
     assert_eq!(count, 4); // ensure that one key package is deleted
+
+    // a client with key packages left can be deleted
+
+    let res = client
+        .delete(&format!("/v1/client/{}", client_uuid))
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let count: i64 = sqlx::query("SELECT COUNT(*) as count FROM key_package;")
+        .fetch_one(db.pool())
+        .await
+        .unwrap()
+        .get("count");
+
+    assert_eq!(count, 0);
 }
 
 // negative tests
@@ -604,3 +624,97 @@ async fn test_replace_key_packages_id() {
 
     assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
+
+#[derive(Default)]
+struct RecordingPushService {
+    sent: Mutex<Vec<String>>,
+}
+
+impl PushService for RecordingPushService {
+    fn send(&self, tokens: &[String]) -> Result<(), PushError> {
+        self.sent.lock().unwrap().extend_from_slice(tokens);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_push_token() {
+    let db = TempDatabase::new().await;
+    let push_service = Arc::new(RecordingPushService::default());
+    let client = start_server_with_push(db.pool().clone(), push_service.clone()).await;
+
+    let mut bearers = Vec::new();
+    let mut client_uuids = Vec::new();
+    for name in ["alice", "bob"] {
+        let created_user = CreateUser {
+            email: format!("{}@gmail.com", name),
+            username: format!("{}_username", name),
+            password: "testpassword".to_string(),
+            name: name.to_string(),
+            identity: Base64(PUBLIC.to_vec()),
+        };
+        let (token, _) = helper::initialize_user(db.pool(), &client, &created_user)
+            .await
+            .unwrap();
+        let bearer = format!("Bearer {}", token);
+        let (_, client_uuid) = create_client(PUBLIC, PRIVATE, &bearer, &client).await;
+        bearers.push(bearer);
+        client_uuids.push(client_uuid);
+    }
+    let (alice_bearer, bob_bearer) = (&bearers[0], &bearers[1]);
+    let bob_uuid = client_uuids[1];
+
+    let payload = RegisterPushToken {
+        token: "bob_token".to_string(),
+    };
+
+    // alice can't register a token for bob's client
+    let res = client
+        .put(&format!("/v1/client/{}/push_token", bob_uuid))
+        .header("Authorization", alice_bearer)
+        .json(&payload)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .put(&format!("/v1/client/{}/push_token", bob_uuid))
+        .header("Authorization", bob_bearer)
+        .json(&payload)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let message = SendMessage {
+        client_uuids: vec![bob_uuid],
+        message: Message {
+            message: Base64(b"test message".to_vec()),
+        },
+        group_uuid: None,
+    };
+    let res = client
+        .post("/v1/message")
+        .header("Authorization", alice_bearer)
+        .json(&message)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(*push_service.sent.lock().unwrap(), vec!["bob_token"]);
+
+    // bob is no longer woken up once the token is revoked
+    let res = client
+        .delete(&format!("/v1/client/{}/push_token", bob_uuid))
+        .header("Authorization", bob_bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .post("/v1/message")
+        .header("Authorization", alice_bearer)
+        .json(&message)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(push_service.sent.lock().unwrap().len(), 1);
+}
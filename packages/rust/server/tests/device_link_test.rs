@@ -0,0 +1,200 @@
+use crate::crypto_helper::{PRIVATE, PUBLIC};
+use crate::helper::{start_server, TempDatabase};
+use axum::http::StatusCode;
+use axum_test_helper::TestClient;
+use common::base64::Base64;
+use common::device_link;
+use common::http_types::{
+    CreateDeviceLink, CreateDeviceLinkResponse, CreateUser, DeviceLink as DeviceLinkBody,
+    UpdateDeviceLink,
+};
+use server::models::device_link::DeviceLink;
+use sqlx::types::chrono::{NaiveDateTime, Utc};
+use uuid::Uuid;
+
+mod crypto_helper;
+mod helper;
+
+async fn create_user_with_client(
+    db: &TempDatabase,
+    client: &TestClient,
+    name: &str,
+) -> (String, Uuid) {
+    let created_user = CreateUser {
+        email: format!("{}@gmail.com", name),
+        username: format!("{}_username", name),
+        password: "test_password".to_string(),
+        name: name.to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), client, &created_user)
+        .await
+        .unwrap();
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, client).await;
+    (bearer, client_uuid)
+}
+
+async fn update(
+    client: &TestClient,
+    bearer: &str,
+    code: &str,
+    update: &UpdateDeviceLink,
+) -> StatusCode {
+    client
+        .patch(&format!("/v1/device_link/{}", code))
+        .header("Authorization", bearer)
+        .json(update)
+        .send()
+        .await
+        .status()
+}
+
+#[tokio::test]
+async fn test_device_link() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (bearer, alice_client) = create_user_with_client(&db, &client, "alice").await;
+    let (bob_bearer, bob_client) = create_user_with_client(&db, &client, "bob").await;
+
+    let public_key = vec![1, 2, 3, 4];
+    let res = client
+        .post("/v1/device_link")
+        .header("Authorization", bearer.clone())
+        .json(&CreateDeviceLink {
+            public_key: Base64(public_key.clone()),
+        })
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let res: CreateDeviceLinkResponse = res.json().await;
+    let code = res.code;
+    assert_eq!(code, device_link::code(&public_key));
+
+    // the code is derived from the key, so a key can only be linked once at a time
+    let res = client
+        .post("/v1/device_link")
+        .header("Authorization", bearer.clone())
+        .json(&CreateDeviceLink {
+            public_key: Base64(public_key.clone()),
+        })
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // links are only visible to the user that created them
+    let res = client
+        .get(&format!("/v1/device_link/{}", code))
+        .header("Authorization", bob_bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = client
+        .get(&format!("/v1/device_link/{}", code))
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let link: DeviceLinkBody = res.json().await;
+    assert_eq!(link.public_key.0, public_key);
+    assert!(link.encrypted_key.is_none());
+    assert!(link.client_uuid.is_none());
+
+    let encrypted_key = UpdateDeviceLink {
+        encrypted_key: Some(Base64(vec![5, 6, 7, 8])),
+        client_uuid: None,
+    };
+    let status = update(&client, &bearer, &code, &encrypted_key).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // the key can't be replaced
+    let status = update(&client, &bearer, &code, &encrypted_key).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let status = update(
+        &client,
+        &bearer,
+        &code,
+        &UpdateDeviceLink {
+            encrypted_key: None,
+            client_uuid: Some(bob_client),
+        },
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = update(
+        &client,
+        &bearer,
+        &code,
+        &UpdateDeviceLink {
+            encrypted_key: None,
+            client_uuid: Some(alice_client),
+        },
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let res = client
+        .get(&format!("/v1/device_link/{}", code))
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    let link: DeviceLinkBody = res.json().await;
+    assert_eq!(link.encrypted_key.unwrap().0, vec![5, 6, 7, 8]);
+    assert_eq!(link.client_uuid, Some(alice_client));
+
+    let res = client
+        .delete(&format!("/v1/device_link/{}", code))
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(&format!("/v1/device_link/{}", code))
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_device_link_expiry() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (bearer, _) = create_user_with_client(&db, &client, "alice").await;
+
+    let res = client
+        .post("/v1/device_link")
+        .header("Authorization", bearer.clone())
+        .json(&CreateDeviceLink {
+            public_key: Base64(vec![1, 2, 3, 4]),
+        })
+        .send()
+        .await;
+    let res: CreateDeviceLinkResponse = res.json().await;
+
+    let purged = DeviceLink::purge_expired(db.pool(), Utc::now().naive_utc())
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+
+    let purged = DeviceLink::purge_expired(
+        db.pool(),
+        NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + 11 * 60, 0).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(purged, 1);
+
+    let res = client
+        .get(&format!("/v1/device_link/{}", res.code))
+        .header("Authorization", bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
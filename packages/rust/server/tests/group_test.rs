@@ -0,0 +1,515 @@
+use crate::crypto_helper::{PRIVATE, PUBLIC};
+use crate::helper::{start_server, TempDatabase};
+use axum::http::StatusCode;
+use axum_test_helper::{TestClient, TestResponse};
+use common::base64::Base64;
+use common::http_types::{
+    CheckMessages, CommitConflict, CreateGroup, CreateUser, Message, MessagesResponse,
+    SendGroupMessage,
+};
+use openmls::prelude::*;
+use openmls_basic_credential::SignatureKeyPair;
+use openmls_rust_crypto::OpenMlsRustCrypto;
+use uuid::Uuid;
+
+mod crypto_helper;
+mod helper;
+
+async fn create_user_with_client(
+    db: &TempDatabase,
+    client: &TestClient,
+    name: &str,
+) -> (String, Uuid) {
+    let (bearer, client_uuid, _) = create_user_with_signer(db, client, name).await;
+    (bearer, client_uuid)
+}
+
+async fn create_user_with_signer(
+    db: &TempDatabase,
+    client: &TestClient,
+    name: &str,
+) -> (String, Uuid, SignatureKeyPair) {
+    let created_user = CreateUser {
+        email: format!("{}@gmail.com", name),
+        username: format!("{}_username", name),
+        password: "test_password".to_string(),
+        name: name.to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), client, &created_user)
+        .await
+        .unwrap();
+    let bearer = format!("Bearer {}", token);
+    let (signer, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, client).await;
+    (bearer, client_uuid, signer)
+}
+
+async fn get_messages(client: &TestClient, bearer: &str, client_uuid: Uuid) -> MessagesResponse {
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
+    let res = client
+        .get("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    res.json().await
+}
+
+async fn create_group(
+    client: &TestClient,
+    bearer: &str,
+    group_uuid: Uuid,
+    client_uuid: Uuid,
+) -> TestResponse {
+    client
+        .post(&format!("/v1/group/{}", group_uuid))
+        .header("Content-Type", "application/json")
+        .body(
+            serde_json::to_string(&CreateGroup {
+                client_uuid,
+                members: vec![],
+            })
+            .unwrap(),
+        )
+        .header("Authorization", bearer)
+        .send()
+        .await
+}
+
+async fn send_group_message(
+    client: &TestClient,
+    bearer: &str,
+    client_uuid: Uuid,
+    group_uuid: Uuid,
+    message: Vec<u8>,
+    exclude_client_uuids: Vec<Uuid>,
+    members: Option<Vec<Uuid>>,
+) -> TestResponse {
+    let message = SendGroupMessage {
+        client_uuid,
+        message: Message {
+            message: Base64(message),
+        },
+        exclude_client_uuids,
+        members,
+    };
+    client
+        .post(&format!("/v1/group/{}/message", group_uuid))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&message).unwrap())
+        .header("Authorization", bearer)
+        .send()
+        .await
+}
+
+// a local mls group that produces a commit for each epoch, the server only looks at the epoch
+struct Committer {
+    backend: OpenMlsRustCrypto,
+    signer: SignatureKeyPair,
+    group: MlsGroup,
+}
+
+impl Committer {
+    fn new(signer: SignatureKeyPair, group_uuid: Uuid) -> Self {
+        let backend = OpenMlsRustCrypto::default();
+        let group = MlsGroup::new_with_group_id(
+            &backend,
+            &signer,
+            &MlsGroupConfig::default(),
+            GroupId::from_slice(group_uuid.as_bytes()),
+            CredentialWithKey {
+                credential: Credential::new(b"alice".to_vec(), CredentialType::Basic).unwrap(),
+                signature_key: SignaturePublicKey::from(signer.public()),
+            },
+        )
+        .unwrap();
+        Self {
+            backend,
+            signer,
+            group,
+        }
+    }
+
+    // a commit for the current epoch, replacing any pending one
+    fn commit(&mut self) -> Vec<u8> {
+        self.group.clear_pending_commit();
+        let (commit, _, _) = self.group.self_update(&self.backend, &self.signer).unwrap();
+        commit.tls_serialize_detached().unwrap()
+    }
+
+    fn merge(&mut self) {
+        self.group.merge_pending_commit(&self.backend).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_group_create() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (alice_bearer, alice_uuid) = create_user_with_client(&db, &client, "alice").await;
+    let (bob_bearer, bob_uuid) = create_user_with_client(&db, &client, "bob").await;
+
+    let group_uuid = Uuid::new_v4();
+
+    // bob can't create a group for alice's client
+    let res = create_group(&client, &bob_bearer, group_uuid, alice_uuid).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = create_group(&client, &alice_bearer, group_uuid, alice_uuid).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // the group can't be taken over by creating it again
+    let res = create_group(&client, &bob_bearer, group_uuid, bob_uuid).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let res = send_group_message(
+        &client,
+        &bob_bearer,
+        bob_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = create_group(&client, &alice_bearer, Uuid::new_v4(), Uuid::new_v4()).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_group_register() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (alice_bearer, alice_uuid) = create_user_with_client(&db, &client, "alice").await;
+    let (bob_bearer, bob_uuid) = create_user_with_client(&db, &client, "bob").await;
+
+    // a group from before the server knew about groups, one of its clients was deleted since
+    let group_uuid = Uuid::new_v4();
+    let res = client
+        .post(&format!("/v1/group/{}", group_uuid))
+        .header("Content-Type", "application/json")
+        .body(
+            serde_json::to_string(&CreateGroup {
+                client_uuid: alice_uuid,
+                members: vec![bob_uuid, Uuid::new_v4()],
+            })
+            .unwrap(),
+        )
+        .header("Authorization", alice_bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![alice_uuid],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let messages = get_messages(&client, &bob_bearer, bob_uuid).await.messages;
+    assert_eq!(messages.len(), 1);
+    assert_eq!("test message".as_bytes().to_vec(), messages[0].message.0);
+}
+
+#[tokio::test]
+async fn test_group_message() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (alice_bearer, alice_uuid, alice_signer) =
+        create_user_with_signer(&db, &client, "alice").await;
+    let (bob_bearer, bob_uuid) = create_user_with_client(&db, &client, "bob").await;
+    let (charlie_bearer, charlie_uuid) = create_user_with_client(&db, &client, "charlie").await;
+
+    let group_uuid = Uuid::new_v4();
+    let res = create_group(&client, &alice_bearer, group_uuid, alice_uuid).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let mut committer = Committer::new(alice_signer, group_uuid);
+
+    // the members only change with a commit
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![],
+        Some(vec![alice_uuid, bob_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        committer.commit(),
+        vec![],
+        Some(vec![alice_uuid, Uuid::new_v4()]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // alice adds bob
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        committer.commit(),
+        vec![alice_uuid],
+        Some(vec![alice_uuid, bob_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    committer.merge();
+
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![alice_uuid],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // the commit went to the members before it, bob gets the welcome instead
+    let messages = get_messages(&client, &bob_bearer, bob_uuid).await.messages;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].group_uuid, Some(group_uuid));
+    assert_eq!("test message".as_bytes().to_vec(), messages[0].message.0);
+
+    let messages = get_messages(&client, &alice_bearer, alice_uuid)
+        .await
+        .messages;
+    assert_eq!(messages.len(), 0);
+
+    // charlie isn't a member so he can't send to the group or change its members
+    let res = send_group_message(
+        &client,
+        &charlie_bearer,
+        charlie_uuid,
+        group_uuid,
+        committer.commit(),
+        vec![],
+        Some(vec![charlie_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let messages = get_messages(&client, &charlie_bearer, charlie_uuid)
+        .await
+        .messages;
+    assert_eq!(messages.len(), 0);
+
+    // the sending client has to be a member, not just another client of a member's user
+    let (_, alice_other_uuid) =
+        helper::create_client(PUBLIC, PRIVATE, &alice_bearer, &client).await;
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_other_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // and it has to belong to the user
+    let res = send_group_message(
+        &client,
+        &charlie_bearer,
+        alice_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_group_member_removed() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (alice_bearer, alice_uuid, alice_signer) =
+        create_user_with_signer(&db, &client, "alice").await;
+    let (bob_bearer, bob_uuid) = create_user_with_client(&db, &client, "bob").await;
+
+    let group_uuid = Uuid::new_v4();
+    let res = create_group(&client, &alice_bearer, group_uuid, alice_uuid).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let mut committer = Committer::new(alice_signer, group_uuid);
+
+    for client_uuids in [vec![alice_uuid, bob_uuid], vec![alice_uuid]] {
+        let res = send_group_message(
+            &client,
+            &alice_bearer,
+            alice_uuid,
+            group_uuid,
+            committer.commit(),
+            vec![alice_uuid],
+            Some(client_uuids),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        committer.merge();
+    }
+
+    // bob still gets the commit that removes him
+    let messages = get_messages(&client, &bob_bearer, bob_uuid).await.messages;
+    assert_eq!(messages.len(), 1);
+
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let messages = get_messages(&client, &alice_bearer, alice_uuid)
+        .await
+        .messages;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].group_uuid, Some(group_uuid));
+
+    let messages = get_messages(&client, &bob_bearer, bob_uuid).await.messages;
+    assert_eq!(messages.len(), 1);
+
+    // unknown group
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        Uuid::new_v4(),
+        b"test message".to_vec(),
+        vec![],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_group_commit_conflict() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let (alice_bearer, alice_uuid, alice_signer) =
+        create_user_with_signer(&db, &client, "alice").await;
+    let (bob_bearer, bob_uuid) = create_user_with_client(&db, &client, "bob").await;
+
+    let group_uuid = Uuid::new_v4();
+    let res = create_group(&client, &alice_bearer, group_uuid, alice_uuid).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let mut committer = Committer::new(alice_signer, group_uuid);
+
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        committer.commit(),
+        vec![alice_uuid],
+        Some(vec![alice_uuid, bob_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    committer.merge();
+
+    // two different commits for the same epoch
+    let first_commit = committer.commit();
+    let second_commit = committer.commit();
+
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        first_commit.clone(),
+        vec![],
+        Some(vec![alice_uuid, bob_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // the second commit loses, the winner is returned and its members aren't applied
+    let res = send_group_message(
+        &client,
+        &bob_bearer,
+        bob_uuid,
+        group_uuid,
+        second_commit,
+        vec![],
+        Some(vec![bob_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let conflict: CommitConflict = res.json().await;
+    assert_eq!(conflict.commit.0, first_commit);
+
+    // retrying the winner is fine
+    let res = send_group_message(
+        &client,
+        &alice_bearer,
+        alice_uuid,
+        group_uuid,
+        first_commit.clone(),
+        vec![],
+        Some(vec![alice_uuid, bob_uuid]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // only the winner was delivered, and alice is still a member
+    let messages = get_messages(&client, &bob_bearer, bob_uuid).await.messages;
+    assert_eq!(messages.len(), 2);
+    assert!(messages
+        .iter()
+        .all(|message| message.message.0 == first_commit));
+    let messages = get_messages(&client, &alice_bearer, alice_uuid)
+        .await
+        .messages;
+    assert_eq!(messages.len(), 2);
+
+    // anything that isn't a commit isn't ordered
+    let res = send_group_message(
+        &client,
+        &bob_bearer,
+        bob_uuid,
+        group_uuid,
+        b"test message".to_vec(),
+        vec![],
+        None,
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
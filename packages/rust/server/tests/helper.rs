@@ -9,7 +9,7 @@ use std::sync::Arc;
 use server::models::user::User;
 use server::router;
 
-use server::types::{DbPool, SIGNATURE_SCHEME};
+use server::types::{DbPool, PushServiceArc, SIGNATURE_SCHEME};
 use sqlx::postgres::PgPoolOptions;
 
 use axum::http::StatusCode;
@@ -19,6 +19,7 @@ use server::models::confirmation::Confirmation;
 use server::models::session::Session;
 use server::services::email::EmailService;
 use server::services::email::PrinterEmailService;
+use server::services::push::PrinterPushService;
 
 use common::base64::Base64;
 use common::http_types::{
@@ -59,8 +60,12 @@ impl TempDatabase {
 }
 
 pub async fn start_server(pool: DbPool) -> TestClient {
+    start_server_with_push(pool, Arc::new(PrinterPushService::default())).await
+}
+
+pub async fn start_server_with_push(pool: DbPool, push_service: PushServiceArc) -> TestClient {
     let email_service = Arc::new(PrinterEmailService::default());
-    let router = router::router(pool, email_service);
+    let router = router::router(pool, email_service, push_service);
 
     TestClient::new(router)
 }
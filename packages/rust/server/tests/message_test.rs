@@ -2,7 +2,13 @@ use crate::crypto_helper::{generate_ed25519_keypair, PRIVATE, PUBLIC};
 use crate::helper::{start_server, TempDatabase};
 use axum::http::StatusCode;
 use common::base64::Base64;
-use common::http_types::{CheckMessages, CreateUser, Message, MessagesResponse, SendMessage};
+use common::http_types::{
+    AckMessages, CheckMessages, CreateUser, DeliveredMessage, Message, MessagesResponse,
+    SendMessage,
+};
+use server::config::CONFIG;
+use server::models::message::Message as ServerMessage;
+use sqlx::types::chrono::{NaiveDateTime, Utc};
 use uuid::Uuid;
 
 mod crypto_helper;
@@ -27,7 +33,11 @@ async fn test_single_message() {
     let bearer = format!("Bearer {}", token);
     let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, &client).await;
 
-    let request_messages = CheckMessages { client_uuid };
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
     let res = client
         .get("/v1/message")
         .header("Content-Type", "application/json")
@@ -44,6 +54,7 @@ async fn test_single_message() {
         message: Message {
             message: Base64(testmessage1.as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -92,6 +103,7 @@ async fn test_multiple_messages() {
         message: Message {
             message: Base64("test message 1".as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -108,6 +120,7 @@ async fn test_multiple_messages() {
         message: Message {
             message: Base64("test message 2".as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -124,6 +137,7 @@ async fn test_multiple_messages() {
         message: Message {
             message: Base64("test message 3".as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -135,7 +149,11 @@ async fn test_multiple_messages() {
 
     assert_eq!(res.status(), StatusCode::OK);
 
-    let request_messages = CheckMessages { client_uuid };
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
     let res = client
         .get("/v1/message")
         .header("Content-Type", "application/json")
@@ -193,6 +211,7 @@ async fn test_multiparticipant() {
         message: Message {
             message: Base64(testmessage1.as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -208,6 +227,8 @@ async fn test_multiparticipant() {
 
     let request_messages = CheckMessages {
         client_uuid: alice_uuid,
+        limit: None,
+        cursor: None,
     };
     let res = client
         .get("/v1/message")
@@ -220,9 +241,25 @@ async fn test_multiparticipant() {
     let messages = res.json::<MessagesResponse>().await.messages;
     assert_eq!(messages.len(), 1);
 
+    let ack = AckMessages {
+        client_uuid: alice_uuid,
+        message_ids: vec![messages[0].id],
+        expired_ids: vec![],
+    };
+    let res = client
+        .post("/v1/message/ack")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&ack).unwrap())
+        .header("Authorization", &alice_bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
     // alice should have 0 messages
     let request_messages = CheckMessages {
         client_uuid: alice_uuid,
+        limit: None,
+        cursor: None,
     };
     let res = client
         .get("/v1/message")
@@ -238,6 +275,8 @@ async fn test_multiparticipant() {
     // bob should have 1 message
     let request_messages = CheckMessages {
         client_uuid: bob_uuid,
+        limit: None,
+        cursor: None,
     };
     let res = client
         .get("/v1/message")
@@ -250,9 +289,25 @@ async fn test_multiparticipant() {
     let messages = res.json::<MessagesResponse>().await.messages;
     assert_eq!(messages.len(), 1);
 
+    let ack = AckMessages {
+        client_uuid: bob_uuid,
+        message_ids: vec![messages[0].id],
+        expired_ids: vec![],
+    };
+    let res = client
+        .post("/v1/message/ack")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&ack).unwrap())
+        .header("Authorization", &bob_bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
     // bob should have 0 messages
     let request_messages = CheckMessages {
         client_uuid: bob_uuid,
+        limit: None,
+        cursor: None,
     };
     let res = client
         .get("/v1/message")
@@ -289,6 +344,8 @@ async fn test_invalid_uuid() {
 
     let request_messages = CheckMessages {
         client_uuid: bad_uuid,
+        limit: None,
+        cursor: None,
     };
     let res = client
         .get("/v1/message")
@@ -305,6 +362,7 @@ async fn test_invalid_uuid() {
         message: Message {
             message: Base64("test message".as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -321,6 +379,7 @@ async fn test_invalid_uuid() {
         message: Message {
             message: Base64("test message".as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -337,6 +396,7 @@ async fn test_invalid_uuid() {
         message: Message {
             message: Base64("test message".as_bytes().to_vec()),
         },
+        group_uuid: None,
     };
     let res = client
         .post("/v1/message")
@@ -397,7 +457,11 @@ async fn test_bad_user() {
     )
     .await;
 
-    let request_messages = CheckMessages { client_uuid };
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
     let res = client
         .get("/v1/message")
         .header("Content-Type", "application/json")
@@ -408,3 +472,420 @@ async fn test_bad_user() {
 
     assert_eq!(res.status(), StatusCode::FORBIDDEN);
 }
+
+#[tokio::test]
+async fn test_message_stream() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let created_user = CreateUser {
+        email: "test@gmail.com".to_string(),
+        username: "test_username".to_string(),
+        password: "test_password".to_string(),
+        name: "test_name".to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), &client, &created_user)
+        .await
+        .unwrap();
+
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, &client).await;
+
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
+    let mut stream = client
+        .get("/v1/message/stream")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(stream.status(), StatusCode::OK);
+
+    let testmessage1 = "test message";
+    let message = SendMessage {
+        client_uuids: vec![client_uuid],
+        message: Message {
+            message: Base64(testmessage1.as_bytes().to_vec()),
+        },
+        group_uuid: None,
+    };
+    let res = client
+        .post("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&message).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let event = stream.chunk_text().await.unwrap();
+    let data = event
+        .lines()
+        .find_map(|line| line.strip_prefix("data:"))
+        .unwrap();
+    let delivered: DeliveredMessage = serde_json::from_str(data.trim()).unwrap();
+    assert_eq!(testmessage1.as_bytes().to_vec(), delivered.message.0);
+
+    // streamed messages stay queued until they are acknowledged
+    let res = client
+        .get("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let messages = res.json::<MessagesResponse>().await.messages;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, delivered.id);
+}
+
+#[tokio::test]
+async fn test_message_stream_bad_user() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let created_user = CreateUser {
+        email: "test@gmail.com".to_string(),
+        username: "test_username".to_string(),
+        password: "test_password".to_string(),
+        name: "test_name".to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), &client, &created_user)
+        .await
+        .unwrap();
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, &client).await;
+
+    let bad_keypair = generate_ed25519_keypair();
+    let bad_user = CreateUser {
+        email: "bad@gmail.com".to_string(),
+        username: "bad_username".to_string(),
+        password: "bad_password".to_string(),
+        name: "bad_name".to_string(),
+        identity: Base64(bad_keypair.public.to_bytes().to_vec()),
+    };
+    let (bad_token, _) = helper::initialize_user(db.pool(), &client, &bad_user)
+        .await
+        .unwrap();
+    let bad_bearer = format!("Bearer {}", bad_token);
+
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
+    let res = client
+        .get("/v1/message/stream")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bad_bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_ack_messages() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let created_user = CreateUser {
+        email: "test@gmail.com".to_string(),
+        username: "test_username".to_string(),
+        password: "test_password".to_string(),
+        name: "test_name".to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), &client, &created_user)
+        .await
+        .unwrap();
+
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, &client).await;
+
+    for i in 0..2 {
+        let message = SendMessage {
+            client_uuids: vec![client_uuid],
+            message: Message {
+                message: Base64(format!("test message {}", i).as_bytes().to_vec()),
+            },
+            group_uuid: None,
+        };
+        let res = client
+            .post("/v1/message")
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&message).unwrap())
+            .header("Authorization", bearer.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    // unacknowledged messages are delivered again
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let res = client
+            .get("/v1/message")
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&request_messages).unwrap())
+            .header("Authorization", bearer.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let messages = res.json::<MessagesResponse>().await.messages;
+        assert_eq!(messages.len(), 2);
+        ids = messages.iter().map(|message| message.id).collect();
+    }
+
+    // only the acknowledged message is removed
+    let ack = AckMessages {
+        client_uuid,
+        message_ids: vec![ids[0]],
+        expired_ids: vec![],
+    };
+    let res = client
+        .post("/v1/message/ack")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&ack).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let messages = res.json::<MessagesResponse>().await.messages;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, ids[1]);
+    assert_eq!("test message 1".as_bytes().to_vec(), messages[0].message.0);
+
+    // another user can't acknowledge our messages
+    let bad_keypair = generate_ed25519_keypair();
+    let bad_user = CreateUser {
+        email: "bad@gmail.com".to_string(),
+        username: "bad_username".to_string(),
+        password: "bad_password".to_string(),
+        name: "bad_name".to_string(),
+        identity: Base64(bad_keypair.public.to_bytes().to_vec()),
+    };
+    let (bad_token, _) = helper::initialize_user(db.pool(), &client, &bad_user)
+        .await
+        .unwrap();
+    let bad_bearer = format!("Bearer {}", bad_token);
+
+    let ack = AckMessages {
+        client_uuid,
+        message_ids: vec![ids[1]],
+        expired_ids: vec![],
+    };
+    let res = client
+        .post("/v1/message/ack")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&ack).unwrap())
+        .header("Authorization", bad_bearer)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_message_pagination() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let created_user = CreateUser {
+        email: "test@gmail.com".to_string(),
+        username: "test_username".to_string(),
+        password: "test_password".to_string(),
+        name: "test_name".to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), &client, &created_user)
+        .await
+        .unwrap();
+
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, &client).await;
+
+    for i in 0..5 {
+        let message = SendMessage {
+            client_uuids: vec![client_uuid],
+            message: Message {
+                message: Base64(format!("test message {}", i).as_bytes().to_vec()),
+            },
+            group_uuid: None,
+        };
+        let res = client
+            .post("/v1/message")
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&message).unwrap())
+            .header("Authorization", bearer.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    let mut cursor = None;
+    let mut received = Vec::new();
+    for expected in [(2, true), (2, true), (1, false)] {
+        let request_messages = CheckMessages {
+            client_uuid,
+            limit: Some(2),
+            cursor,
+        };
+        let res = client
+            .get("/v1/message")
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&request_messages).unwrap())
+            .header("Authorization", bearer.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let page = res.json::<MessagesResponse>().await;
+        assert_eq!(page.messages.len(), expected.0);
+        assert_eq!(page.has_more, expected.1);
+        assert_eq!(page.next_cursor, page.messages.last().map(|m| m.id));
+        cursor = page.next_cursor;
+        received.extend(page.messages);
+    }
+
+    for (i, message) in received.iter().enumerate() {
+        assert_eq!(
+            format!("test message {}", i).as_bytes().to_vec(),
+            message.message.0
+        );
+    }
+
+    // nothing is left after the last page
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: Some(2),
+        cursor,
+    };
+    let res = client
+        .get("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let page = res.json::<MessagesResponse>().await;
+    assert_eq!(page.messages.len(), 0);
+    assert!(!page.has_more);
+    assert_eq!(page.next_cursor, None);
+}
+
+#[tokio::test]
+async fn test_expired_messages() {
+    let db = TempDatabase::new().await;
+    let client = start_server(db.pool().clone()).await;
+
+    let created_user = CreateUser {
+        email: "test@gmail.com".to_string(),
+        username: "test_username".to_string(),
+        password: "test_password".to_string(),
+        name: "test_name".to_string(),
+        identity: Base64(PUBLIC.to_vec()),
+    };
+    let (token, _) = helper::initialize_user(db.pool(), &client, &created_user)
+        .await
+        .unwrap();
+
+    let bearer = format!("Bearer {}", token);
+    let (_, client_uuid) = helper::create_client(PUBLIC, PRIVATE, &bearer, &client).await;
+
+    for _ in 0..2 {
+        let message = SendMessage {
+            client_uuids: vec![client_uuid],
+            message: Message {
+                message: Base64("test message".as_bytes().to_vec()),
+            },
+            group_uuid: None,
+        };
+        let res = client
+            .post("/v1/message")
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&message).unwrap())
+            .header("Authorization", bearer.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    // nothing has expired yet
+    let purged = ServerMessage::purge_expired(db.pool(), Utc::now().naive_utc())
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+
+    let purged = ServerMessage::purge_expired(
+        db.pool(),
+        NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() + CONFIG.message_ttl + 1, 0)
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(purged, 2);
+
+    let request_messages = CheckMessages {
+        client_uuid,
+        limit: None,
+        cursor: None,
+    };
+    let res = client
+        .get("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = res.json::<MessagesResponse>().await;
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(res.expired.len(), 1);
+    assert_eq!(res.expired[0].amount, 2);
+    assert_eq!(res.expired[0].group_uuid, None);
+
+    let ack = AckMessages {
+        client_uuid,
+        message_ids: vec![],
+        expired_ids: vec![res.expired[0].id],
+    };
+    let res = client
+        .post("/v1/message/ack")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&ack).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get("/v1/message")
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&request_messages).unwrap())
+        .header("Authorization", bearer.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.json::<MessagesResponse>().await.expired.len(), 0);
+}